@@ -0,0 +1,33 @@
+use anyhow::Result;
+use code_timing_macros::time_snippet;
+use std::fs::File;
+use std::io::BufReader;
+
+// Day 07 is a binary, so its solvers are pulled in as a module.
+#[allow(dead_code)]
+#[path = "../src/bin/07.rs"]
+mod day07;
+
+use day07::{calibration, calibration_backward, Add, Concat, Mul, Operator};
+
+const ROUNDS: usize = 20;
+
+fn main() -> Result<()> {
+    let equations = day07::parse(BufReader::new(File::open(day07::INPUT_FILE)?))?;
+
+    // Forward against backward on the parsed input, repeated to even out noise.
+    for (name, ops) in [
+        ("+ *", vec![&Add as &dyn Operator, &Mul]),
+        ("+ * ||", vec![&Add as &dyn Operator, &Mul, &Concat]),
+    ] {
+        println!("=== {}, {} rounds each ===", name, ROUNDS);
+        println!("--- Forward ---");
+        let forward = time_snippet!((0..ROUNDS).map(|_| calibration(&equations, &ops)).last());
+        println!("--- Backward ---");
+        let backward = time_snippet!((0..ROUNDS)
+            .map(|_| calibration_backward(&equations, &ops))
+            .last());
+        assert_eq!(forward, backward);
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use adv_code_2024::chunked::ChunkMap;
+use adv_code_2024::*;
+use code_timing_macros::time_snippet;
+
+// Plain row-major storage over a fixed rectangle, the baseline for dense worlds.
+struct DenseGrid<Tile> {
+    rect: Rect,
+    cells: Vec<Option<Tile>>,
+}
+
+impl<Tile: Clone> DenseGrid<Tile> {
+    fn new(rect: Rect) -> Self {
+        DenseGrid {
+            rect,
+            cells: vec![None; rect.area()],
+        }
+    }
+    fn insert(&mut self, point: Point<i32>, tile: Tile) {
+        let index = self.rect.index(&point).expect("point outside the grid");
+        self.cells[index] = Some(tile);
+    }
+    fn get(&self, point: &Point<i32>) -> Option<&Tile> {
+        self.cells[self.rect.index(point)?].as_ref()
+    }
+}
+
+// A 1000x1000 block, every cell filled.
+fn dense() -> Vec<Point<i32>> {
+    Rect::new((0, 0).into(), (999, 999).into())
+        .points()
+        .collect()
+}
+
+// 100 blocks of 100x100 cells scattered over a few million units.
+fn sparse() -> Vec<Point<i32>> {
+    (0..100)
+        .flat_map(|i: i32| {
+            let corner = Point::new(
+                (i * 7_919) % 1_000 * 4_001 - 2_000_000,
+                (i * 104_729) % 997 * 3_989,
+            );
+            Rect::new(corner, corner + Point::new(99, 99)).points()
+        })
+        .collect()
+}
+
+fn bench_map(points: &[Point<i32>]) -> i64 {
+    let mut map = Map::new();
+    time_snippet!(points.iter().for_each(|p| map.insert(*p, p.x as i64)));
+    let found: i64 = time_snippet!(points.iter().filter_map(|p| map.get(p)).sum());
+    let total: i64 = time_snippet!(map.iter().map(|(_, v)| *v).sum());
+    assert_eq!(found, total);
+    total
+}
+
+fn bench_chunked(points: &[Point<i32>]) -> i64 {
+    let mut map = ChunkMap::new();
+    time_snippet!(points.iter().for_each(|p| map.insert(*p, p.x as i64)));
+    let found: i64 = time_snippet!(points.iter().filter_map(|p| map.get(p)).sum());
+    let total: i64 = time_snippet!(map.iter().map(|(_, v)| *v).sum());
+    assert_eq!(found, total);
+    println!("{} chunks", map.chunk_count());
+    total
+}
+
+fn bench_dense(points: &[Point<i32>]) -> i64 {
+    let rect = points.iter().fold(Rect::new(points[0], points[0]), |r, p| {
+        Rect::new(r.min.min(p), r.max.max(p))
+    });
+    let mut grid = DenseGrid::new(rect);
+    time_snippet!(points.iter().for_each(|p| grid.insert(*p, p.x as i64)));
+    let found: i64 = time_snippet!(points.iter().filter_map(|p| grid.get(p)).sum());
+    let total: i64 = time_snippet!(grid.cells.iter().flatten().sum());
+    assert_eq!(found, total);
+    total
+}
+
+fn main() {
+    println!("=== Dense 1000x1000 ===");
+    let points = dense();
+    println!("--- HashMap ---");
+    let expected = bench_map(&points);
+    println!("--- Chunked ---");
+    assert_eq!(expected, bench_chunked(&points));
+    println!("--- Dense grid ---");
+    assert_eq!(expected, bench_dense(&points));
+
+    println!("\n=== Sparse, 100 blocks over millions of units ===");
+    let points = sparse();
+    println!("--- HashMap ---");
+    let expected = bench_map(&points);
+    println!("--- Chunked ---");
+    assert_eq!(expected, bench_chunked(&points));
+    // A dense grid over the sparse bounds would need trillions of cells.
+}
@@ -0,0 +1,213 @@
+use crate::{Map, Point};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stencil {
+    Four,
+    Eight,
+    /// Offsets from the cell to each of its neighbours.
+    Custom(Vec<Point<i32>>),
+}
+
+impl Stencil {
+    fn offsets(&self) -> Vec<Point<i32>> {
+        let origin = Point::new(0, 0);
+        match self {
+            Stencil::Four => origin.neighbors4().to_vec(),
+            Stencil::Eight => origin.neighbors8().to_vec(),
+            Stencil::Custom(offsets) => offsets.clone(),
+        }
+    }
+}
+
+/// Generations `start..start + length` repeat forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    pub fn is_fixed_point(&self) -> bool {
+        self.length == 1
+    }
+}
+
+/// Steps every cell of a map at once. The cells are held densely with
+/// their neighbour indices worked out up front, and each generation is
+/// written into a second buffer that is then swapped in.
+pub struct Automaton<Tile> {
+    cells: Vec<Point<i32>>,
+    neighbors: Vec<Vec<usize>>,
+    current: Vec<Tile>,
+    next: Vec<Tile>,
+    generation: usize,
+}
+
+impl<Tile> Automaton<Tile>
+where
+    Tile: Clone,
+{
+    /// Neighbours that fall outside the map are left out of the stencil.
+    pub fn new(map: &Map<Tile>, stencil: Stencil) -> Self {
+        let cells: Vec<Point<i32>> = map
+            .rect()
+            .points()
+            .filter(|p| map.get(p).is_some())
+            .collect();
+        let index: HashMap<Point<i32>, usize> =
+            cells.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+        let offsets = stencil.offsets();
+        let neighbors = cells
+            .iter()
+            .map(|&cell| {
+                offsets
+                    .iter()
+                    .filter_map(|&offset| index.get(&(cell + offset)).copied())
+                    .collect()
+            })
+            .collect();
+        let current: Vec<Tile> = cells.iter().map(|p| map.get(p).unwrap().clone()).collect();
+        let next = current.clone();
+        Automaton {
+            cells,
+            neighbors,
+            current,
+            next,
+            generation: 0,
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i32>, &Tile)> {
+        self.cells.iter().copied().zip(self.current.iter())
+    }
+
+    pub fn to_map(&self) -> Map<Tile> {
+        let mut map = Map::new();
+        for (point, tile) in self.iter() {
+            map.insert(point, tile.clone());
+        }
+        map
+    }
+
+    /// Advances one generation, `rule` getting each cell and its neighbours.
+    /// Returns whether any cell changed.
+    pub fn step(&mut self, mut rule: impl FnMut(&Tile, &[&Tile]) -> Tile) -> bool
+    where
+        Tile: PartialEq,
+    {
+        let mut changed = false;
+        let mut around = Vec::new();
+        for (i, neighbors) in self.neighbors.iter().enumerate() {
+            around.clear();
+            around.extend(neighbors.iter().map(|&n| &self.current[n]));
+            let tile = rule(&self.current[i], &around);
+            changed |= tile != self.current[i];
+            self.next[i] = tile;
+        }
+        std::mem::swap(&mut self.current, &mut self.next);
+        self.generation += 1;
+        changed
+    }
+
+    /// Steps until a generation repeats an earlier one and reports the loop.
+    /// The automaton is left on the first repeated generation.
+    pub fn find_cycle(&mut self, mut rule: impl FnMut(&Tile, &[&Tile]) -> Tile) -> Cycle
+    where
+        Tile: Hash + Eq,
+    {
+        let mut seen = HashMap::new();
+        loop {
+            if let Some(&start) = seen.get(&self.current) {
+                return Cycle {
+                    start,
+                    length: self.generation - start,
+                };
+            }
+            seen.insert(self.current.clone(), self.generation);
+            self.step(&mut rule);
+        }
+    }
+
+    /// Moves to `generation`, skipping whole cycles once one is found.
+    pub fn fast_forward(&mut self, generation: usize, mut rule: impl FnMut(&Tile, &[&Tile]) -> Tile)
+    where
+        Tile: Hash + Eq,
+    {
+        let mut seen: HashMap<Vec<Tile>, usize> = HashMap::new();
+        let mut history: Vec<Vec<Tile>> = Vec::new();
+        let offset = self.generation;
+        while self.generation < generation {
+            if let Some(&start) = seen.get(&self.current) {
+                let length = self.generation - start;
+                let target = start + (generation - start) % length;
+                self.current = history[target - offset].clone();
+                self.generation = generation;
+                return;
+            }
+            seen.insert(self.current.clone(), self.generation);
+            history.push(self.current.clone());
+            self.step(&mut rule);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(board: &str) -> Map<bool> {
+        let chars: Map<char> = Map::parse(board.as_bytes()).unwrap();
+        let mut map = Map::new();
+        for (point, c) in chars.iter() {
+            map.insert(*point, *c == '#');
+        }
+        map
+    }
+
+    fn rule(alive: &bool, around: &[&bool]) -> bool {
+        let n = around.iter().filter(|a| ***a).count();
+        n == 3 || (*alive && n == 2)
+    }
+
+    #[test]
+    fn blinker_cycles() {
+        let start = life(".....\n..#..\n..#..\n..#..\n.....");
+        let mut automaton = Automaton::new(&start, Stencil::Eight);
+        assert!(automaton.step(rule));
+        assert!(automaton.to_map().get(&(1, 2).into()).unwrap());
+
+        let mut automaton = Automaton::new(&start, Stencil::Eight);
+        assert_eq!(
+            Cycle {
+                start: 0,
+                length: 2
+            },
+            automaton.find_cycle(rule)
+        );
+
+        let mut automaton = Automaton::new(&start, Stencil::Eight);
+        automaton.fast_forward(1_000_001, rule);
+        assert_eq!(1_000_001, automaton.generation());
+        assert!(automaton.to_map().get(&(3, 2).into()).unwrap());
+        assert!(!automaton.to_map().get(&(2, 1).into()).unwrap());
+    }
+
+    #[test]
+    fn block_is_fixed() {
+        let mut automaton = Automaton::new(&life("....\n.##.\n.##.\n...."), Stencil::Eight);
+        assert!(automaton.find_cycle(rule).is_fixed_point());
+        assert!(!automaton.step(rule));
+        // With only orthogonal neighbours a blinker dies out.
+        let mut automaton = Automaton::new(&life("...\n.#.\n.#.\n.#.\n..."), Stencil::Four);
+        automaton.step(rule);
+        assert_eq!(1, automaton.iter().filter(|(_, a)| **a).count());
+        automaton.step(rule);
+        assert_eq!(0, automaton.iter().filter(|(_, a)| **a).count());
+    }
+}
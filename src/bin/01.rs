@@ -29,7 +29,7 @@ fn main() -> Result<()> {
         let mut l: Vec<i32> = Vec::new();
         let mut r: Vec<i32> = Vec::new();
         // Reader is a list of number seperated by spaces. for example "1   3".  for each line split the numbers parse them and append them to the l and r
-        reader.lines().flatten().for_each(|line| {
+        reader.lines().map_while(Result::ok).for_each(|line| {
             let mut parts = line.split_whitespace();
             // parse the two numbers and append them to the l and r lists
             l.push(parts.next().unwrap().parse().unwrap());
@@ -45,7 +45,7 @@ fn main() -> Result<()> {
     fn part1_pq<R: BufRead>(reader: R) -> Result<i32> {
         let mut l = PriorityQueue::new();
         let mut r = PriorityQueue::new();
-        reader.lines().flatten().enumerate().for_each(|(_i, line)| {
+        reader.lines().map_while(Result::ok).enumerate().for_each(|(_i, line)| {
             let mut parts = line.split_whitespace();
             // parse the two numbers and append them to the l and r lists
             l.push(_i, parts.next().unwrap().parse::<i32>().unwrap());
@@ -61,7 +61,7 @@ fn main() -> Result<()> {
     fn part1_heap<const N: usize, R: BufRead>(reader: R) -> Result<i32> {
         let mut l = DaryHeap::<_, N>::new();
         let mut r = DaryHeap::<_, N>::new();
-        reader.lines().flatten().for_each(|line| {
+        reader.lines().map_while(Result::ok).for_each(|line| {
             let mut parts = line.split_whitespace();
             // parse the two numbers and append them to the l and r lists
             l.push(parts.next().unwrap().parse::<i32>().unwrap());
@@ -108,7 +108,7 @@ fn main() -> Result<()> {
         // r should be a count of how many times a number appears in a list
         let mut l: Vec<i32> = Vec::new();
         let mut r: HashMap<i32, i32> = HashMap::new();
-        reader.lines().flatten().for_each(|line| {
+        reader.lines().map_while(Result::ok).for_each(|line| {
             let mut parts = line.split_whitespace();
             // parse the two numbers and append them to the l and r lists
             l.push(parts.next().unwrap().parse().unwrap());
@@ -52,7 +52,7 @@ fn main() -> Result<()> {
             self.0
                 .windows(2)
                 .map(|w| (w[1] - w[0]).abs())
-                .all(|d| d >= 1 && d <= 3)
+                .all(|d| (1..=3).contains(&d))
         }
         fn is_safe(&self) -> bool {
             (self.is_increasing() || self.is_decreasing()) && self.safe_distance()
@@ -62,7 +62,7 @@ fn main() -> Result<()> {
     fn part1<R: BufRead>(reader: R) -> Result<usize> {
         let answer = reader
             .lines()
-            .flatten()
+            .map_while(Result::ok)
             .map(Report::from)
             .filter(|r| r.is_safe())
             .count();
@@ -103,7 +103,7 @@ fn main() -> Result<()> {
     fn part2<R: BufRead>(reader: R) -> Result<usize> {
         let answer = reader
             .lines()
-            .flatten()
+            .map_while(Result::ok)
             .map(Report::from)
             .filter(|r| r.is_safe2())
             .count();
@@ -29,11 +29,11 @@ fn main() -> Result<()> {
     fn get_board<R: BufRead>(reader: R) -> Vec<Vec<char>> {
         reader
             .lines()
-            .flatten()
+            .map_while(Result::ok)
             .map(|line| line.chars().collect())
             .collect::<Vec<Vec<char>>>()
     }
-    fn get_chars(board: &Vec<Vec<char>>, words: Vec<(usize, usize)>) -> Option<Vec<char>> {
+    fn get_chars(board: &[Vec<char>], words: Vec<(usize, usize)>) -> Option<Vec<char>> {
         let mut chars = Vec::new();
         for (x, y) in words {
             if let Some(c) = board.get(y).and_then(|row| row.get(x)) {
@@ -49,7 +49,7 @@ fn main() -> Result<()> {
         let mut count = 0;
         let board = get_board(reader);
 
-        fn get_words(board: &Vec<Vec<char>>, x: usize, y: usize) -> Vec<Vec<char>> {
+        fn get_words(board: &[Vec<char>], x: usize, y: usize) -> Vec<Vec<char>> {
             let mut pts = vec![
                 vec![(x, y + 1), (x, y + 2), (x, y + 3)],
                 vec![(x + 1, y), (x + 2, y), (x + 3, y)],
@@ -93,8 +93,8 @@ fn main() -> Result<()> {
         let mut count = 0;
         let board = get_board(reader);
 
-        fn get_words(board: &Vec<Vec<char>>, x: usize, y: usize) -> Vec<Vec<char>> {
-            let mut pts = vec![
+        fn get_words(board: &[Vec<char>], x: usize, y: usize) -> Vec<Vec<char>> {
+            let pts = vec![
                 vec![(x, y), (x, y + 1), (x, y + 2), (x, y + 3)],
                 vec![(x, y), (x + 1, y), (x + 2, y), (x + 3, y)],
                 vec![(x, y), (x + 1, y + 1), (x + 2, y + 2), (x + 3, y + 3)],
@@ -142,7 +142,7 @@ fn main() -> Result<()> {
         let mut count = 0;
         let board = get_board(reader);
 
-        fn get_words(board: &Vec<Vec<char>>, x: usize, y: usize) -> Vec<String> {
+        fn get_words(board: &[Vec<char>], x: usize, y: usize) -> Vec<String> {
             if x < 1 || y < 1 {
                 return Vec::new();
             };
@@ -43,7 +43,7 @@ const TEST_BOOKS: &str = "\
 
 fn parse_orders<R: BufRead>(reader: R) -> HashMap<usize, Vec<usize>> {
     let mut orders = HashMap::new();
-    reader.lines().flatten().for_each(|line| {
+    reader.lines().map_while(Result::ok).for_each(|line| {
         let mut parts = line.split('|');
         let a = parts.next().unwrap().parse().unwrap();
         let b = parts.next().unwrap().parse().unwrap();
@@ -55,12 +55,12 @@ fn parse_orders<R: BufRead>(reader: R) -> HashMap<usize, Vec<usize>> {
 fn parse_books<R: BufRead>(reader: R) -> Vec<Vec<usize>> {
     reader
         .lines()
-        .flatten()
+        .map_while(Result::ok)
         .map(|line| line.split(',').map(|s| s.parse().unwrap()).collect())
         .collect()
 }
 
-fn is_valid(orders: &HashMap<usize, Vec<usize>>, book: &Vec<usize>) -> bool {
+fn is_valid(orders: &HashMap<usize, Vec<usize>>, book: &[usize]) -> bool {
     book.iter().rev().enumerate().all(|(i, page)| {
         let remaining = book
             .iter()
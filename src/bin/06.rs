@@ -1,13 +1,20 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use adv_code_2024::*;
+use adv_code_2024::export::{Exporter, Overlay, Rgb};
+use adv_code_2024::debugger::{Debugger, Heading};
+use adv_code_2024::states::StateSet;
+use adv_code_2024::walker::{Cell, Guard, Rules, Walker};
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Empty};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 const DAY: &str = "06";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+// Where `--export` writes images unless it is given a directory.
+const OUTPUT_DIR: &str = "output";
 
 const TEST: &str = "\
 ....#.....
@@ -22,37 +29,29 @@ const TEST: &str = "\
 ......#...";
 
 // type Point = (i32, i32);
-#[derive(Eq, Hash, PartialEq, Clone)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Tile)]
 enum Tile {
+    #[tile('#')]
     Full,
+    #[tile('.', '^')]
     Empty,
 }
 
-impl TryFrom<char> for Tile {
-    type Error = Error;
-    fn try_from(c: char) -> Result<Self> {
-        match c {
-            '#' => Ok(Tile::Full),
-            '.' => Ok(Tile::Empty),
-            '^' => Ok(Tile::Empty),
-            _ => Err(anyhow!("Invalid character: {}", c))
-        }
-    }
-}
-
 #[derive(Clone)]
 struct State {
     pos: Point<i32>,
     dir: Direction,
 }
 
+impl Heading for State {
+    fn pos(&self) -> Point<i32> {
+        self.pos
+    }
+    fn dir(&self) -> Direction {
+        self.dir
+    }
+}
+
 fn parse<R: BufRead>(reader: R) -> Result<(Map<Tile>, State)> {
     let (map,start) = Map::parse_with_start(reader, &'^')?;
     let start = State {
@@ -61,15 +60,14 @@ fn parse<R: BufRead>(reader: R) -> Result<(Map<Tile>, State)> {
     };
     Ok((map, start))
 }
-fn repr(map: &Map<Tile>, seen:&Seen) -> String {
+fn repr(map: &Map<Tile>, seen:&StateSet) -> String {
     let mut result = String::new();
     for y in 0..=map.max.y {
         for x in 0..=map.max.x {
             let point = (x, y).into();
-            let c = match (map.get(&point), seen.map.contains_key(&point)) {
-                (Some(Tile::Full),_) => '#',
-                (Some(Tile::Empty), false) => '.',
+            let c = match (map.get(&point), seen.contains_cell(&point)) {
                 (Some(Tile::Empty), true) => 'X',
+                (Some(tile), _) => tile.into(),
                 _ => {continue},
 
             };
@@ -81,91 +79,324 @@ fn repr(map: &Map<Tile>, seen:&Seen) -> String {
 }
 
 
-struct Seen {
-    map: HashMap<Point<i32>, HashSet<Direction>>
+fn find_path(map: &Map<Tile>, start: &State) -> (StateSet, bool) {
+    find_path_with(map, start, None)
 }
-impl Seen {
-    fn new() -> Self {
-        Seen {
-            map: HashMap::new()
-        }
-    }
-    fn insert(&mut self, state: &State) {
-        self.map.entry(state.pos).or_insert_with(HashSet::new).insert(state.dir.clone());
-    }
-    fn contains(&self, state: &State) -> bool {
-        self.map.get(&state.pos).map_or(false, |set| set.contains(&state.dir))
-    }
+
+// As find_path, with `obstacle` treated as Full without touching the map.
+fn find_path_with(map: &Map<Tile>, start: &State, obstacle: Option<Point<i32>>) -> (StateSet, bool) {
+    find_path_from(map, StateSet::for_map(map), start, obstacle)
 }
 
-fn find_path(map: &Map<Tile>, start: &State) -> (Seen, bool) {
-    let mut seen = Seen::new();
+// Carries on a walk that has already been through the states in `seen`.
+fn find_path_from(map: &Map<Tile>, mut seen: StateSet, start: &State, obstacle: Option<Point<i32>>) -> (StateSet, bool) {
     let mut state = start.clone();
     loop {
-        if seen.contains(&state) {
+        if !seen.insert(state.pos, state.dir) {
             return (seen, false);
         }
-        seen.insert(&state);
-        let next = match state.dir {
-            Direction::Up => state.pos + Point{ x: 0, y: -1 },
-            Direction::Down => state.pos + Point{ x: 0, y: 1 },
-            Direction::Left => state.pos + Point{ x: -1, y: 0 },
-            Direction::Right => state.pos + Point{ x: 1, y: 0 },
-        };
-        match map.get(&next) {
+        let next = state.pos + state.dir;
+        let tile = if obstacle == Some(next) { Some(&Tile::Full) } else { map.get(&next) };
+        match tile {
             Some(Tile::Empty) => {
                 state.pos = next;
             },
             Some(Tile::Full) => {
-                state.dir = match state.dir {
-                    Direction::Up => Direction::Right,
-                    Direction::Right => Direction::Down,
-                    Direction::Down => Direction::Left,
-                    Direction::Left => Direction::Up,
-                };
+                state.dir = state.dir.turn_right();
             }
             None => {
                 return (seen, true);
             }
         }
     }
-    unreachable!("Infinite loop");
 }
+// For every cell and direction, where the guard ends up when it next has
+// to turn, or None if it walks off the map first.
+struct Jumps {
+    rect: Rect,
+    stops: Vec<Option<Point<i32>>>,
+}
+impl Jumps {
+    fn new(map: &Map<Tile>) -> Self {
+        let rect = map.rect();
+        let mut jumps = Jumps { rect, stops: vec![None; rect.area() * 4] };
+        for dir in Direction::ALL {
+            let step: Point<i32> = dir.into();
+            // Cells furthest along the direction first, so the cell ahead is always done.
+            let mut points: Vec<_> = rect.points().collect();
+            points.sort_by_key(|p| -(p.x * step.x + p.y * step.y));
+            for point in points {
+                let ahead = point + step;
+                let stop = match map.get(&ahead) {
+                    Some(Tile::Full) => Some(point),
+                    Some(Tile::Empty) => jumps.get(&ahead, dir),
+                    None => None,
+                };
+                jumps.set(&point, dir, stop);
+            }
+        }
+        jumps
+    }
+    fn slot(&self, point: &Point<i32>, dir: Direction) -> usize {
+        self.rect.index(point).unwrap() * 4 + dir.index()
+    }
+    fn get(&self, point: &Point<i32>, dir: Direction) -> Option<Point<i32>> {
+        self.stops[self.slot(point, dir)]
+    }
+    fn set(&mut self, point: &Point<i32>, dir: Direction, stop: Option<Point<i32>>) {
+        let slot = self.slot(point, dir);
+        self.stops[slot] = stop;
+    }
+
+    // Only the cells that can walk straight into the new obstacle change.
+    // Returns what they held before, for `undo`.
+    fn insert(&mut self, map: &Map<Tile>, obstacle: Point<i32>) -> Vec<(usize, Option<Point<i32>>)> {
+        let mut changed = Vec::new();
+        for dir in Direction::ALL {
+            let step: Point<i32> = dir.into();
+            let back = Point { x: -step.x, y: -step.y };
+            let stop = obstacle + back;
+            let mut point = stop;
+            while let Some(Tile::Empty) = map.get(&point) {
+                let slot = self.slot(&point, dir);
+                changed.push((slot, self.stops[slot]));
+                self.stops[slot] = Some(stop);
+                point = point + back;
+            }
+        }
+        changed
+    }
+    fn undo(&mut self, changed: Vec<(usize, Option<Point<i32>>)>) {
+        for (slot, stop) in changed {
+            self.stops[slot] = stop;
+        }
+    }
+
+    // Same answer as `!find_path(..).1`, only remembering the turns.
+    fn loops(&self, start: &State) -> bool {
+        let mut turns = HashSet::new();
+        let (mut pos, mut dir) = (start.pos, start.dir);
+        while let Some(stop) = self.get(&pos, dir) {
+            pos = stop;
+            dir = dir.turn_right();
+            if !turns.insert((pos, dir)) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// Every state of the unobstructed walk, in order.
+fn walk(map: &Map<Tile>, start: &State) -> Vec<State> {
+    trace(map, start, None).0
+}
+
+// Every state of the walk in order, up to leaving the map or coming back to
+// a state already seen, with the index where the repeated state first came.
+fn trace(map: &Map<Tile>, start: &State, obstacle: Option<Point<i32>>) -> (Vec<State>, Option<usize>) {
+    let mut seen = StateSet::for_map(map);
+    seen.insert(start.pos, start.dir);
+    let mut states = vec![start.clone()];
+    let mut state = start.clone();
+    loop {
+        let next = state.pos + state.dir;
+        let tile = if obstacle == Some(next) { Some(&Tile::Full) } else { map.get(&next) };
+        match tile {
+            Some(Tile::Empty) => state.pos = next,
+            Some(Tile::Full) => state.dir = state.dir.turn_right(),
+            None => return (states, None),
+        }
+        if !seen.insert(state.pos, state.dir) {
+            let entry = states.iter().position(|s| s.pos == state.pos && s.dir == state.dir);
+            return (states, entry);
+        }
+        states.push(state.clone());
+    }
+}
+
+struct LoopReport {
+    obstacle: Point<i32>,
+    // First state of the walk that is part of the cycle.
+    entry: State,
+    // Number of states round the cycle, turns included.
+    length: usize,
+    // Cells on the cycle in the order the guard first reaches them.
+    cells: Vec<Point<i32>>,
+    path: Vec<Point<i32>>,
+}
+
+fn loop_report(map: &Map<Tile>, start: &State, obstacle: Point<i32>) -> Option<LoopReport> {
+    let (states, entry) = trace(map, start, Some(obstacle));
+    let cycle = &states[entry?..];
+    let mut cells = Vec::new();
+    let mut on_cycle = HashSet::new();
+    for state in cycle {
+        if on_cycle.insert(state.pos) {
+            cells.push(state.pos);
+        }
+    }
+    let mut path: Vec<Point<i32>> = cycle.iter().map(|s| s.pos).collect();
+    path.push(cycle[0].pos);
+    Some(LoopReport {
+        obstacle,
+        entry: cycle[0].clone(),
+        length: cycle.len(),
+        cells,
+        path,
+    })
+}
+
+fn repr_loop(map: &Map<Tile>, report: &LoopReport) -> String {
+    let cells: HashSet<_> = report.cells.iter().collect();
+    let mut result = String::new();
+    for y in 0..=map.max.y {
+        for x in 0..=map.max.x {
+            let point = (x, y).into();
+            let c = match (map.get(&point), cells.contains(&point)) {
+                _ if point == report.obstacle => 'O',
+                _ if point == report.entry.pos => '*',
+                (Some(Tile::Empty), true) => 'X',
+                (Some(tile), _) => tile.into(),
+                _ => {continue},
+            };
+            result.push(c);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn export_loop(dir: &Path, map: &Map<Tile>, report: &LoopReport, name: &str) -> Result<PathBuf> {
+    Exporter::new(dir)
+        .overlay(Overlay::Path { points: report.path.clone(), color: Rgb::RED })
+        .overlay(Overlay::Points { points: vec![report.obstacle], color: Rgb::GREEN })
+        .overlay(Overlay::Points { points: vec![report.entry.pos], color: Rgb::BLUE })
+        .png(name, map, |tile| match tile {
+            Tile::Full => Rgb::WHITE,
+            Tile::Empty => Rgb::BLACK,
+        })
+}
+
+// Cycle length -> number of obstacles giving a loop that long.
+fn histogram(reports: &[LoopReport]) -> BTreeMap<usize, usize> {
+    let mut lengths = BTreeMap::new();
+    for report in reports {
+        *lengths.entry(report.length).or_default() += 1;
+    }
+    lengths
+}
+
+// One move of the guard, for the debugger.
+fn next_state(map: &Map<Tile>, state: &State) -> Option<State> {
+    let next = state.pos + state.dir;
+    match map.get(&next)? {
+        Tile::Empty => Some(State { pos: next, dir: state.dir }),
+        Tile::Full => Some(State { pos: state.pos, dir: state.dir.turn_right() }),
+    }
+}
+
+fn walker_cell(tile: &Tile) -> Cell {
+    match tile {
+        Tile::Full => Cell::Wall,
+        Tile::Empty => Cell::Open,
+    }
+}
+
+// `--export [dir]` writes the day's images, under OUTPUT_DIR by default.
+fn export_dir() -> Option<PathBuf> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--export");
+    args.next()?;
+    let dir = args.next().filter(|arg| !arg.starts_with("--"));
+    Some(dir.unwrap_or(OUTPUT_DIR.to_string()).into())
+}
+
+// `--threads N` for part 2, defaulting to one per core.
+fn thread_count() -> Result<usize> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--threads" {
+            let n: usize = args.next().context("--threads needs a count")?.parse()?;
+            ensure!(n > 0, "--threads must be at least 1");
+            return Ok(n);
+        }
+    }
+    Ok(std::thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
+    let threads = thread_count()?;
+    let export = export_dir();
+
+    // `--debug [input]` steps through the example walk, or the puzzle input,
+    // reading commands from stdin.
+    let mut debug = std::env::args().skip_while(|arg| arg != "--debug");
+    if debug.next().is_some() {
+        let (map, state) = match debug.next().as_deref() {
+            Some("input") => parse(BufReader::new(File::open(INPUT_FILE)?))?,
+            _ => parse(BufReader::new(TEST.as_bytes()))?,
+        };
+        let mut debugger = Debugger::new(&map, state, |s| next_state(&map, s), |tile: &Tile| -> char { tile.into() });
+        return debugger.interactive(std::io::stdin().lock(), &mut std::io::stdout());
+    }
 
     //region Part 1
     println!("=== Part 1 ===");
 
     fn part1<R: BufRead>(reader: R) -> Result<usize> {
 
-        let (map, mut state) = parse(reader)?;
+        let (map, state) = parse(reader)?;
         let (seen,_) = find_path(&map, &state);
+        Ok(seen.cell_count())
+    }
 
-        println!("{}", repr(&map, &seen));
-        Ok(seen.map.len())
+    // The same walk through the configurable walker.
+    fn part1_walker<R: BufRead>(reader: R) -> Result<usize> {
+        let (map, state) = parse(reader)?;
+        let walker = Walker::new(&map, walker_cell, Rules::default())?;
+        let outcome = walker.run(vec![Guard::new(state.pos, state.dir)]);
+        Ok(outcome.visited.cell_count())
     }
 
     // TODO: Set the expected answer for the test input
     assert_eq!(41, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(41, part1_walker(BufReader::new(TEST.as_bytes()))?);
+    let (map, state) = parse(BufReader::new(TEST.as_bytes()))?;
+    println!("{}", repr(&map, &find_path(&map, &state).0));
 
     let input_file = BufReader::new(File::open(INPUT_FILE)?);
     let result = time_snippet!(part1(input_file)?);
     println!("Result = {}", result);
+
+    let input_file = BufReader::new(File::open(INPUT_FILE)?);
+    assert_eq!(result, time_snippet!(part1_walker(input_file)?));
+
+    if let Some(dir) = &export {
+        let (map, state) = parse(BufReader::new(File::open(INPUT_FILE)?))?;
+        let (seen, _) = find_path(&map, &state);
+        let path = Exporter::new(dir)
+            .overlay(Overlay::Points { points: seen.cells().collect(), color: Rgb::RED })
+            .png(DAY, &map, |tile| match tile {
+                Tile::Full => Rgb::WHITE,
+                Tile::Empty => Rgb::BLACK,
+            })?;
+        println!("Map written to {}", path.display());
+    }
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
     fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let (map, mut state) = parse(reader)?;
+        let (map, state) = parse(reader)?;
         let (seen,_) = find_path(&map, &state);
 
         let mut count :usize = 0;
-        let start_point = state.pos.clone();
-        for point in seen.map.keys().filter(|&p| *p != start_point) {
+        let start_point = state.pos;
+        for point in seen.cells().filter(|p| *p != start_point) {
             let mut map = map.clone();
-            map.insert(point.clone(), Tile::Full);
+            map.insert(point, Tile::Full);
             let (_,offmap) = find_path(&map, &state);
             if !offmap {
                 count += 1;
@@ -175,11 +406,124 @@ fn main() -> Result<()> {
         Ok(count)
     }
 
+    fn part2_jumps<R: BufRead>(reader: R) -> Result<usize> {
+        let (map, state) = parse(reader)?;
+        let (seen,_) = find_path(&map, &state);
+        let mut jumps = Jumps::new(&map);
+
+        let mut count :usize = 0;
+        for point in seen.cells().filter(|p| *p != state.pos) {
+            let changed = jumps.insert(&map, point);
+            if jumps.loops(&state) {
+                count += 1;
+            }
+            jumps.undo(changed);
+        }
+
+        Ok(count)
+    }
+
+    // Each worker takes an even share of the candidates and reads the same
+    // map, so the total doesn't depend on the number of threads.
+    fn part2_parallel<R: BufRead>(reader: R, threads: usize) -> Result<usize> {
+        let (map, state) = parse(reader)?;
+        let (seen,_) = find_path(&map, &state);
+        let candidates: Vec<Point<i32>> = seen.cells().filter(|p| *p != state.pos).collect();
+        let chunk = candidates.len().div_ceil(threads).max(1);
+
+        let count = std::thread::scope(|scope| {
+            let workers: Vec<_> = candidates.chunks(chunk).map(|chunk| {
+                let (map, state) = (&map, &state);
+                scope.spawn(move || {
+                    chunk.iter().filter(|&&p| !find_path_with(map, state, Some(p)).1).count()
+                })
+            }).collect();
+            workers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+
+        Ok(count)
+    }
+
+    // Up to the first time the guard reaches an obstacle's cell the walk is
+    // the same as without it, so each check picks up from the state just
+    // before that. Cells are only tried on their first visit: an obstacle
+    // there would have blocked the guard earlier on.
+    fn part2_resume<R: BufRead>(reader: R) -> Result<usize> {
+        let (map, state) = parse(reader)?;
+        let states = walk(&map, &state);
+
+        let mut count :usize = 0;
+        let mut visited = HashSet::from([state.pos]);
+        let mut prefix = StateSet::for_map(&map);
+        for (before, current) in states.iter().zip(&states[1..]) {
+            if visited.insert(current.pos) {
+                let (_, offmap) = find_path_from(&map, prefix.clone(), before, Some(current.pos));
+                if !offmap {
+                    count += 1;
+                }
+            }
+            prefix.insert(before.pos, before.dir);
+        }
+
+        Ok(count)
+    }
+
+    fn part2_report<R: BufRead>(reader: R) -> Result<(Map<Tile>, Vec<LoopReport>)> {
+        let (map, state) = parse(reader)?;
+        let (seen,_) = find_path(&map, &state);
+        let reports = seen.cells()
+            .filter(|p| *p != state.pos)
+            .filter_map(|p| loop_report(&map, &state, p))
+            .collect();
+        Ok((map, reports))
+    }
+
     assert_eq!(6, part2(BufReader::new(TEST.as_bytes()))?);
+    let (map, reports) = part2_report(BufReader::new(TEST.as_bytes()))?;
+    assert_eq!(6, reports.len());
+    for report in &reports {
+        println!(
+            "obstacle {} enters loop at {} facing {:?}, {} states over {} cells",
+            report.obstacle, report.entry.pos, report.entry.dir, report.length, report.cells.len()
+        );
+    }
+    println!("{}", repr_loop(&map, &reports[0]));
+    assert_eq!(6, part2_resume(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(6, part2_jumps(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(6, part2_parallel(BufReader::new(TEST.as_bytes()), 3)?);
 
     let input_file = BufReader::new(File::open(INPUT_FILE)?);
     let result = time_snippet!(part2(input_file)?);
     println!("Result = {}", result);
+
+    let input_file = BufReader::new(File::open(INPUT_FILE)?);
+    let jump_result = time_snippet!(part2_jumps(input_file)?);
+    assert_eq!(result, jump_result);
+
+    // `--loops` reports every loop on the input: how many there are of each
+    // cycle length, and with `--export` an image of the longest one.
+    if std::env::args().any(|arg| arg == "--loops") {
+        let input_file = BufReader::new(File::open(INPUT_FILE)?);
+        let (map, reports) = time_snippet!(part2_report(input_file)?);
+        assert_eq!(result, reports.len());
+        println!("Cycle lengths:");
+        for (length, count) in histogram(&reports) {
+            println!("{:>6}: {}", length, count);
+        }
+        if let (Some(dir), Some(longest)) = (&export, reports.iter().max_by_key(|r| r.length)) {
+            let path = export_loop(dir, &map, longest, concatcp!(DAY, "_loop"))?;
+            println!("Longest loop written to {}", path.display());
+        }
+    }
+
+    let input_file = BufReader::new(File::open(INPUT_FILE)?);
+    let resume_result = time_snippet!(part2_resume(input_file)?);
+    assert_eq!(result, resume_result);
+
+    println!("Using {} threads", threads);
+    let input_file = BufReader::new(File::open(INPUT_FILE)?);
+    let parallel_result = time_snippet!(part2_parallel(input_file, threads)?);
+    assert_eq!(result, parallel_result);
     //endregion
 
     Ok(())
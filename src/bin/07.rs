@@ -6,7 +6,7 @@ use const_format::concatcp;
 use adv_code_2024::*;
 
 const DAY: &str = "07";
-const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+pub(crate) const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
 
 const TEST: &str = "\
 190: 10 19
@@ -19,46 +19,304 @@ const TEST: &str = "\
 21037: 9 7 18 13
 292: 11 6 16 20";
 
-fn main() -> Result<()> {
-    start_day(DAY);
+pub(crate) struct Equation {
+    target: usize,
+    operands: Vec<usize>,
+}
 
-    //region Part 1
-    println!("=== Part 1 ===");
+pub(crate) fn parse<R: BufRead>(reader: R) -> Result<Vec<Equation>> {
+    reader.lines().map(|line| {
+        let line = line?;
+        let (target, operands) = line.split_once(": ").context("missing ': '")?;
+        let operands = operands.split(' ').map(str::parse).collect::<Result<_, _>>()?;
+        Ok(Equation { target: target.parse()?, operands })
+    }).collect()
+}
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let lines: Vec<(usize,Vec<usize>)> = reader.lines().flatten().map(|line| {
-            let mut parts = line.split(": ");
-            let ans = parts.next().unwrap().parse().unwrap();
-            let parts = parts.next().unwrap().split(' ').map(|s| s.parse().unwrap()).rev().collect();
-            (ans, parts)
-        }).collect();
-        println!("len of parts {}", lines.iter().map(|(_,p)| p.len()).max().unwrap());
-        fn values(parts: &mut Vec<usize>, max: &usize) -> Vec<usize> {
-            let mut ans = vec![parts.pop().unwrap()];
-            while let Some(v) = parts.pop() {
-                ans = ans
-                    .iter()
-                    .map(|a| vec![a+v, a*v])
-                    .flatten()
-                    .filter(|a| a <= max)
-                    .collect();
-            }
-            ans
+// Where the left operand comes from when an operator is undone.
+pub(crate) enum Inverse {
+    // No left operand gives the result.
+    Impossible,
+    Left(usize),
+    // Several might, or the operator can't say; search forwards instead.
+    Unknown,
+}
+
+pub(crate) trait Operator {
+    fn symbol(&self) -> &'static str;
+    // None when the result isn't a usize.
+    fn apply(&self, left: usize, right: usize) -> Option<usize>;
+    // The left operand `l` with `apply(l, right) == Some(result)`.
+    fn inverse(&self, _result: usize, _right: usize) -> Inverse {
+        Inverse::Unknown
+    }
+    // Whether the result is never below the left operand when both are at
+    // least 1, so anything past the target can be dropped.
+    fn grows(&self) -> bool {
+        false
+    }
+}
+
+fn exact(left: Option<usize>) -> Inverse {
+    left.map_or(Inverse::Impossible, Inverse::Left)
+}
+
+pub(crate) struct Add;
+impl Operator for Add {
+    fn symbol(&self) -> &'static str { "+" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { left.checked_add(right) }
+    fn inverse(&self, result: usize, right: usize) -> Inverse { exact(result.checked_sub(right)) }
+    fn grows(&self) -> bool { true }
+}
+
+pub(crate) struct Mul;
+impl Operator for Mul {
+    fn symbol(&self) -> &'static str { "*" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { left.checked_mul(right) }
+    fn inverse(&self, result: usize, right: usize) -> Inverse {
+        match right {
+            0 if result == 0 => Inverse::Unknown,
+            0 => Inverse::Impossible,
+            _ => exact((result % right == 0).then(|| result / right)),
         }
+    }
+    fn grows(&self) -> bool { true }
+}
+
+pub(crate) struct Concat;
+fn shift(right: usize) -> usize {
+    10usize.pow(right.checked_ilog10().unwrap_or(0) + 1)
+}
+impl Operator for Concat {
+    fn symbol(&self) -> &'static str { "||" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> {
+        left.checked_mul(shift(right))?.checked_add(right)
+    }
+    fn inverse(&self, result: usize, right: usize) -> Inverse {
+        let shift = shift(right);
+        exact(result.checked_sub(right).filter(|rest| rest % shift == 0).map(|rest| rest / shift))
+    }
+    fn grows(&self) -> bool { true }
+}
 
-        let mut count = 0;
-        let mut max =0;
-        for (ans, mut parts) in lines {
-            let vals = values(&mut parts, &ans);
-            if vals.len() > max {
-                max = vals.len();
+struct Sub;
+impl Operator for Sub {
+    fn symbol(&self) -> &'static str { "-" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { left.checked_sub(right) }
+    fn inverse(&self, result: usize, right: usize) -> Inverse { exact(result.checked_add(right)) }
+}
+
+// Integer division, rounding down.
+struct Div;
+impl Operator for Div {
+    fn symbol(&self) -> &'static str { "/" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { left.checked_div(right) }
+}
+
+struct Pow;
+impl Operator for Pow {
+    fn symbol(&self) -> &'static str { "**" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { left.checked_pow(right.try_into().ok()?) }
+}
+
+struct Xor;
+impl Operator for Xor {
+    fn symbol(&self) -> &'static str { "^" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { Some(left ^ right) }
+    fn inverse(&self, result: usize, right: usize) -> Inverse { Inverse::Left(result ^ right) }
+}
+
+struct Min;
+impl Operator for Min {
+    fn symbol(&self) -> &'static str { "min" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { Some(left.min(right)) }
+}
+
+struct Max;
+impl Operator for Max {
+    fn symbol(&self) -> &'static str { "max" }
+    fn apply(&self, left: usize, right: usize) -> Option<usize> { Some(left.max(right)) }
+    fn grows(&self) -> bool { true }
+}
+
+const OPERATORS: &[&dyn Operator] = &[&Add, &Mul, &Concat, &Sub, &Div, &Pow, &Xor, &Min, &Max];
+
+// Looks up a space-separated list of operator symbols, e.g. "+ * ||".
+fn operators(symbols: &str) -> Result<Vec<&'static dyn Operator>> {
+    symbols.split_whitespace().map(|symbol| {
+        OPERATORS.iter().copied().find(|op| op.symbol() == symbol)
+            .with_context(|| format!("unknown operator {symbol:?}"))
+    }).collect()
+}
+
+// Every value the operands can make, evaluated left to right.
+fn values(operands: &[usize], ops: &[&dyn Operator], max: usize) -> Vec<usize> {
+    let prune = ops.iter().all(|op| op.grows()) && operands.iter().all(|&v| v >= 1);
+    let Some((first, rest)) = operands.split_first() else { return Vec::new() };
+    let mut ans = vec![*first];
+    for &v in rest {
+        ans = ans
+            .iter()
+            .flat_map(|&a| ops.iter().filter_map(move |op| op.apply(a, v)))
+            .filter(|&a| !prune || a <= max)
+            .collect();
+    }
+    ans
+}
+
+// Sum of the targets that some choice of operators can reach.
+pub(crate) fn calibration(equations: &[Equation], ops: &[&dyn Operator]) -> usize {
+    equations.iter()
+        .filter(|eq| values(&eq.operands, ops, eq.target).contains(&eq.target))
+        .map(|eq| eq.target)
+        .sum()
+}
+
+// Depth-first over the values `acc` and the remaining operands can make
+// left to right, `path` holding the index in `ops` of each operator used
+// so far. Stops as soon as `found` returns true.
+fn any_value(
+    acc: usize,
+    operands: &[usize],
+    ops: &[&dyn Operator],
+    path: &mut Vec<usize>,
+    found: &mut impl FnMut(usize, &[usize]) -> bool,
+) -> bool {
+    let Some((&v, rest)) = operands.split_first() else { return found(acc, path) };
+    for (i, op) in ops.iter().enumerate() {
+        if let Some(next) = op.apply(acc, v) {
+            path.push(i);
+            let done = any_value(next, rest, ops, path, found);
+            path.pop();
+            if done {
+                return true;
             }
-            if vals.contains(&ans) {
-                count += ans;
+        }
+    }
+    false
+}
+
+// Works back from the target, undoing the last operand with each operator's
+// inverse. Targets that can't be undone cut the branch off. `visit` gets
+// the operator indices of each way through, first operator first, and ends
+// the search by returning true. Only the recursion and the operators chosen
+// so far (in `undone`, last first) are kept, so memory is linear in the
+// number of operands.
+fn solutions(
+    target: usize,
+    operands: &[usize],
+    ops: &[&dyn Operator],
+    undone: &mut Vec<usize>,
+    visit: &mut impl FnMut(&[usize]) -> bool,
+) -> bool {
+    let Some((&last, rest)) = operands.split_last() else { return false };
+    let Some((&first, middle)) = rest.split_first() else {
+        let chosen: Vec<usize> = undone.iter().rev().copied().collect();
+        return target == last && visit(&chosen);
+    };
+    for (i, op) in ops.iter().enumerate() {
+        undone.push(i);
+        let done = match op.inverse(target, last) {
+            Inverse::Impossible => false,
+            Inverse::Left(left) => solutions(left, rest, ops, undone, visit),
+            Inverse::Unknown => any_value(first, middle, ops, &mut Vec::new(), &mut |v, before| {
+                op.apply(v, last) == Some(target)
+                    && visit(&before.iter().chain(undone.iter().rev()).copied().collect::<Vec<_>>())
+            }),
+        };
+        undone.pop();
+        if done {
+            return true;
+        }
+    }
+    false
+}
+
+fn solvable(target: usize, operands: &[usize], ops: &[&dyn Operator]) -> bool {
+    solutions(target, operands, ops, &mut Vec::new(), &mut |_| true)
+}
+
+// e.g. "81 * 40 + 27 = 3267"
+fn expression(eq: &Equation, ops: &[&dyn Operator], chosen: &[usize]) -> String {
+    let mut text = eq.operands[0].to_string();
+    for (v, &i) in eq.operands[1..].iter().zip(chosen) {
+        text.push_str(&format!(" {} {}", ops[i].symbol(), v));
+    }
+    format!("{} = {}", text, eq.target)
+}
+
+struct Explanation {
+    target: usize,
+    operands: Vec<usize>,
+    // Every distinct choice of operators that makes the target.
+    count: usize,
+    // The first of them, or all of them when enumerating.
+    expressions: Vec<String>,
+}
+
+fn explain(eq: &Equation, ops: &[&dyn Operator], all: bool) -> Explanation {
+    let mut count = 0;
+    let mut expressions = Vec::new();
+    solutions(eq.target, &eq.operands, ops, &mut Vec::new(), &mut |chosen| {
+        count += 1;
+        if all || expressions.is_empty() {
+            expressions.push(expression(eq, ops, chosen));
+        }
+        false
+    });
+    Explanation { target: eq.target, operands: eq.operands.clone(), count, expressions }
+}
+
+impl Explanation {
+    fn text(&self) -> String {
+        match self.count {
+            0 => format!("{}: impossible with {:?}", self.target, self.operands),
+            n => format!("{}: {} way(s), {}", self.target, n, self.expressions.join("; ")),
+        }
+    }
+
+    fn json(&self) -> String {
+        let operands: Vec<String> = self.operands.iter().map(|v| v.to_string()).collect();
+        let expressions: Vec<String> = self.expressions.iter().map(|e| format!("\"{}\"", e)).collect();
+        format!(
+            "{{\"target\":{},\"operands\":[{}],\"count\":{},\"expressions\":[{}]}}",
+            self.target, operands.join(","), self.count, expressions.join(",")
+        )
+    }
+}
+
+// The value following `name` on the command line.
+fn flag(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+pub(crate) fn calibration_backward(equations: &[Equation], ops: &[&dyn Operator]) -> usize {
+    equations.iter()
+        .filter(|eq| solvable(eq.target, &eq.operands, ops))
+        .map(|eq| eq.target)
+        .sum()
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // Every inverse has to undo its operator.
+    for op in OPERATORS {
+        for (left, right) in [(12, 34), (7, 1), (100, 10), (0, 5)] {
+            let Some(result) = op.apply(left, right) else { continue };
+            match op.inverse(result, right) {
+                Inverse::Left(undone) => assert_eq!(left, undone, "{} {} {}", left, op.symbol(), right),
+                Inverse::Impossible => panic!("{} {} {} can't be undone", left, op.symbol(), right),
+                Inverse::Unknown => {}
             }
         }
-        println!("max {}", max);
-        Ok(count)
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    fn part1<R: BufRead>(reader: R) -> Result<usize> {
+        Ok(calibration(&parse(reader)?, &[&Add, &Mul]))
     }
 
     assert_eq!(3749, part1(BufReader::new(TEST.as_bytes()))?);
@@ -66,55 +324,17 @@ fn main() -> Result<()> {
     let input_file = BufReader::new(File::open(INPUT_FILE)?);
     let result = time_snippet!(part1(input_file)?);
     println!("Result = {}", result);
+
+    let equations = parse(BufReader::new(File::open(INPUT_FILE)?))?;
+    assert_eq!(3749, calibration_backward(&parse(BufReader::new(TEST.as_bytes()))?, &[&Add, &Mul]));
+    assert_eq!(result, time_snippet!(calibration_backward(&equations, &[&Add, &Mul])));
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
     fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let lines: Vec<(usize,Vec<usize>)> = reader.lines().flatten().map(|line| {
-            let mut parts = line.split(": ");
-            let ans = parts.next().unwrap().parse().unwrap();
-            let parts = parts.next().unwrap().split(' ').map(|s| s.parse().unwrap()).rev().collect();
-            (ans, parts)
-        }).collect();
-
-        fn concat(l:&usize, r:usize) -> usize {
-            let up = match r {
-                _ if r>=1000 => 10000,
-                _ if r>=100 => 1000,
-                _ if r>=10 => 100,
-                _ => 10,
-            };
-            l*up + r
-        }
-        fn values(parts: &mut Vec<usize>, max: &usize) -> Vec<usize> {
-            let mut ans = vec![parts.pop().unwrap()];
-            while let Some(v) = parts.pop() {
-                ans = ans
-                    .iter()
-                    .map(|a| vec![a+v, a*v, concat(a,v)])
-                    .flatten()
-                    .filter(|a| a <= max)
-                    .collect();
-            }
-            ans
-        }
-
-        let mut count = 0;
-        let mut max = 0;
-        for (ans, mut parts) in lines {
-            let vals = values(&mut parts, &ans);
-            if vals.len() > max {
-                max = vals.len();
-            }
-            if vals.contains(&ans) {
-                count += ans;
-            }
-        }
-        println!("max {}", max);
-
-        Ok(count)
+        Ok(calibration(&parse(reader)?, &[&Add, &Mul, &Concat]))
     }
 
     assert_eq!(11387, part2(BufReader::new(TEST.as_bytes()))?);
@@ -122,7 +342,51 @@ fn main() -> Result<()> {
     let input_file = BufReader::new(File::open(INPUT_FILE)?);
     let result = time_snippet!(part2(input_file)?);
     println!("Result = {}", result);
+
+    assert_eq!(11387, calibration_backward(&parse(BufReader::new(TEST.as_bytes()))?, &[&Add, &Mul, &Concat]));
+    assert_eq!(result, time_snippet!(calibration_backward(&equations, &[&Add, &Mul, &Concat])));
     //endregion
 
+    let example = parse(BufReader::new(TEST.as_bytes()))?;
+    let found = explain(&example[1], &[&Add, &Mul], true);
+    assert_eq!(2, found.count);
+    assert_eq!(vec!["81 * 40 + 27 = 3267", "81 + 40 * 27 = 3267"], found.expressions);
+    assert_eq!("7290: 1 way(s), 6 * 8 || 6 * 15 = 7290", explain(&example[4], &[&Add, &Mul, &Concat], false).text());
+    assert_eq!(0, explain(&example[2], &[&Add, &Mul, &Concat], false).count);
+    assert_eq!(
+        r#"{"target":292,"operands":[11,6,16,20],"count":1,"expressions":["11 + 6 * 16 + 20 = 292"]}"#,
+        explain(&example[8], &[&Add, &Mul], false).json()
+    );
+
+    // `--explain text|json` shows how each example line can be made, every
+    // way of making it with `--all`, and the puzzle input with `--input`.
+    if let Some(format) = flag("--explain") {
+        let ops = operators(&flag("--ops").unwrap_or("+ * ||".to_string()))?;
+        let all = std::env::args().any(|arg| arg == "--all");
+        let equations = match std::env::args().any(|arg| arg == "--input") {
+            true => parse(BufReader::new(File::open(INPUT_FILE)?))?,
+            false => example,
+        };
+        println!();
+        for eq in &equations {
+            let explanation = explain(eq, &ops, all);
+            match format.as_str() {
+                "json" => println!("{}", explanation.json()),
+                "text" => println!("{}", explanation.text()),
+                _ => bail!("--explain takes text or json, not {format:?}"),
+            }
+        }
+    }
+
+    // `--ops "<symbols>"` runs the input with any mix of operators.
+    if let Some(symbols) = flag("--ops") {
+        let ops = operators(&symbols)?;
+        println!("\n=== Operators {} ===", symbols);
+        let equations = parse(BufReader::new(File::open(INPUT_FILE)?))?;
+        let result = time_snippet!(calibration(&equations, &ops));
+        println!("Result = {}", result);
+        assert_eq!(result, time_snippet!(calibration_backward(&equations, &ops)));
+    }
+
     Ok(())
 }
@@ -0,0 +1,293 @@
+use crate::{Grid, Map, Point, Rect};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// A boolean grid packed one bit per cell, each row stored as a run of
+/// `u64` words. Cells are addressed in map coordinates, offset by `origin`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    pub origin: Point<i32>,
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::with_origin((0, 0).into(), width, height)
+    }
+
+    pub fn with_origin(origin: Point<i32>, width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        BitGrid {
+            origin,
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn rect(&self) -> Rect {
+        let max = self.origin + Point::new(self.width as i32 - 1, self.height as i32 - 1);
+        Rect::new(self.origin, max)
+    }
+
+    fn locate(&self, point: &Point<i32>) -> Option<(usize, u64)> {
+        let x = usize::try_from(point.x - self.origin.x)
+            .ok()
+            .filter(|&x| x < self.width)?;
+        let y = usize::try_from(point.y - self.origin.y)
+            .ok()
+            .filter(|&y| y < self.height)?;
+        Some((y * self.stride + x / 64, 1 << (x % 64)))
+    }
+
+    /// Cells outside the grid read as unset.
+    pub fn get(&self, point: &Point<i32>) -> bool {
+        self.locate(point)
+            .is_some_and(|(word, bit)| self.words[word] & bit != 0)
+    }
+
+    pub fn set(&mut self, point: Point<i32>, value: bool) {
+        let (word, bit) = self.locate(&point).expect("point outside the grid");
+        if value {
+            self.words[word] |= bit;
+        } else {
+            self.words[word] &= !bit;
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn iter_ones(&self) -> impl Iterator<Item = Point<i32>> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let (y, base) = (i / self.stride, (i % self.stride) * 64);
+            let origin = self.origin;
+            std::iter::successors(Some(word).filter(|&w| w != 0), |&w| {
+                Some(w & (w - 1)).filter(|&w| w != 0)
+            })
+            .map(move |w| {
+                origin + Point::new((base + w.trailing_zeros() as usize) as i32, y as i32)
+            })
+        })
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    // Clears the padding bits past `width` in the last word of every row.
+    fn trim(&mut self) {
+        if self.width % 64 == 0 {
+            return;
+        }
+        let mask = (1u64 << (self.width % 64)) - 1;
+        for y in 0..self.height {
+            *self.row_mut(y).last_mut().unwrap() &= mask;
+        }
+    }
+
+    /// Moves every cell `n` columns towards larger x, dropping cells that
+    /// fall off the edge.
+    pub fn shift_right(&mut self, n: usize) {
+        let (words, bits) = (n / 64, n % 64);
+        for y in 0..self.height {
+            let row = self.row_mut(y);
+            for i in (0..row.len()).rev() {
+                let low = i.checked_sub(words).map_or(0, |j| row[j]);
+                let carry = i
+                    .checked_sub(words + 1)
+                    .filter(|_| bits > 0)
+                    .map_or(0, |j| row[j] >> (64 - bits));
+                row[i] = (low << bits) | carry;
+            }
+        }
+        self.trim();
+    }
+
+    /// Moves every cell `n` columns towards smaller x.
+    pub fn shift_left(&mut self, n: usize) {
+        let (words, bits) = (n / 64, n % 64);
+        for y in 0..self.height {
+            let row = self.row_mut(y);
+            let len = row.len();
+            for i in 0..len {
+                let high = row.get(i + words).copied().unwrap_or(0);
+                let carry = row
+                    .get(i + words + 1)
+                    .filter(|_| bits > 0)
+                    .map_or(0, |w| w << (64 - bits));
+                row[i] = (high >> bits) | carry;
+            }
+        }
+    }
+
+    /// Moves every cell `n` rows towards larger y.
+    pub fn shift_down(&mut self, n: usize) {
+        let n = n.min(self.height) * self.stride;
+        let len = self.words.len();
+        self.words.copy_within(0..len - n, n);
+        self.words[..n].fill(0);
+    }
+
+    /// Moves every cell `n` rows towards smaller y.
+    pub fn shift_up(&mut self, n: usize) {
+        let n = n.min(self.height) * self.stride;
+        let len = self.words.len();
+        self.words.copy_within(n.., 0);
+        self.words[len - n..].fill(0);
+    }
+
+    fn zip_with(&mut self, other: &BitGrid, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "grid sizes differ"
+        );
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word = op(*word, *other);
+        }
+    }
+}
+
+impl From<&Map<bool>> for BitGrid {
+    fn from(map: &Map<bool>) -> Self {
+        let rect = map.rect();
+        let mut grid = BitGrid::with_origin(rect.min, rect.width(), rect.height());
+        for (point, &value) in map.iter() {
+            if value {
+                grid.set(*point, true);
+            }
+        }
+        grid
+    }
+}
+
+impl From<&BitGrid> for Map<bool> {
+    fn from(grid: &BitGrid) -> Self {
+        let mut map = Map::new();
+        for point in grid.rect().points() {
+            map.insert(point, grid.get(&point));
+        }
+        map
+    }
+}
+
+impl Grid for BitGrid {
+    type Tile = bool;
+
+    /// Every cell inside the grid is present, set or not.
+    fn get(&self, point: &Point<i32>) -> Option<&bool> {
+        let (word, bit) = self.locate(point)?;
+        Some(if self.words[word] & bit != 0 {
+            &true
+        } else {
+            &false
+        })
+    }
+
+    fn rect(&self) -> Rect {
+        BitGrid::rect(self)
+    }
+}
+
+impl Not for BitGrid {
+    type Output = BitGrid;
+    fn not(mut self) -> BitGrid {
+        self.words.iter_mut().for_each(|w| *w = !*w);
+        self.trim();
+        self
+    }
+}
+
+macro_rules! bit_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign:ident, $op:tt) => {
+        impl $assign_trait<&BitGrid> for BitGrid {
+            fn $assign(&mut self, other: &BitGrid) {
+                self.zip_with(other, |a, b| a $op b);
+            }
+        }
+        impl $trait<&BitGrid> for BitGrid {
+            type Output = BitGrid;
+            fn $method(mut self, other: &BitGrid) -> BitGrid {
+                self.$assign(other);
+                self
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifts_and_ops() {
+        // Wide enough that rows span two words.
+        let mut grid = BitGrid::new(70, 3);
+        grid.set((0, 0).into(), true);
+        grid.set((63, 1).into(), true);
+        grid.set((69, 2).into(), true);
+        assert_eq!(3, grid.count_ones());
+
+        let mut moved = grid.clone();
+        moved.shift_right(1);
+        assert!(moved.get(&(1, 0).into()) && moved.get(&(64, 1).into()));
+        assert_eq!(2, moved.count_ones());
+        moved.shift_left(64);
+        assert_eq!(
+            vec![Point::new(0, 1)],
+            moved.iter_ones().collect::<Vec<_>>()
+        );
+        moved.shift_up(1);
+        assert!(moved.get(&(0, 0).into()));
+        moved.shift_down(2);
+        assert!(moved.get(&(0, 2).into()));
+
+        let both = grid.clone() | &moved;
+        assert_eq!(4, both.count_ones());
+        assert_eq!(1, (both.clone() & &moved).count_ones());
+        assert_eq!(3, (both ^ &moved).count_ones());
+        assert_eq!(70 * 3 - 3, (!grid).count_ones());
+    }
+
+    #[test]
+    fn map_round_trip() {
+        let mut map = Map::new();
+        for (i, point) in Rect::new((-2, 1).into(), (3, 4).into())
+            .points()
+            .enumerate()
+        {
+            map.insert(point, i % 3 == 0);
+        }
+        let grid = BitGrid::from(&map);
+        assert_eq!(map.iter().filter(|(_, &v)| v).count(), grid.count_ones());
+        let back = Map::from(&grid);
+        assert!(map.iter().all(|(p, v)| back.get(p) == Some(v)));
+    }
+
+    #[test]
+    fn grid_queries() {
+        let mut grid = BitGrid::with_origin((-1, 0).into(), 8, 2);
+        grid.set((5, 1).into(), true);
+        assert_eq!(Some(&false), Grid::get(&grid, &(0, 1).into()));
+        assert_eq!(None, Grid::get(&grid, &(7, 1).into()));
+        assert_eq!(
+            Some(((5, 1).into(), 6)),
+            grid.cast((-1, 1).into(), crate::Direction::Right, |&wall| wall)
+        );
+    }
+}
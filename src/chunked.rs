@@ -0,0 +1,155 @@
+use crate::{Grid, Point, Rect};
+use std::collections::HashMap;
+
+const CHUNK: i32 = 16;
+const CHUNK_CELLS: usize = (CHUNK * CHUNK) as usize;
+
+/// A map for huge or far-flung coordinates: cells live in dense
+/// `CHUNK x CHUNK` blocks that are only allocated once something is
+/// written inside them.
+#[derive(Clone)]
+pub struct ChunkMap<Tile> {
+    chunks: HashMap<Point<i32>, Box<[Option<Tile>]>>,
+    len: usize,
+    pub min: Point<i32>,
+    pub max: Point<i32>,
+}
+
+fn split(point: &Point<i32>) -> (Point<i32>, usize) {
+    let chunk = Point::new(point.x.div_euclid(CHUNK), point.y.div_euclid(CHUNK));
+    let index = point.y.rem_euclid(CHUNK) * CHUNK + point.x.rem_euclid(CHUNK);
+    (chunk, index as usize)
+}
+
+impl<Tile> Default for ChunkMap<Tile> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Tile> ChunkMap<Tile> {
+    pub fn new() -> Self {
+        ChunkMap {
+            chunks: HashMap::new(),
+            len: 0,
+            min: (0, 0).into(),
+            max: (0, 0).into(),
+        }
+    }
+
+    pub fn get(&self, point: &Point<i32>) -> Option<&Tile> {
+        let (chunk, index) = split(point);
+        self.chunks.get(&chunk)?[index].as_ref()
+    }
+
+    /// Unlike `Map`, the bounds cover only the inserted cells rather than
+    /// always including the origin.
+    pub fn insert(&mut self, point: Point<i32>, tile: Tile) {
+        if self.len == 0 {
+            (self.min, self.max) = (point, point);
+        } else {
+            self.min = self.min.min(&point);
+            self.max = self.max.max(&point);
+        }
+        let (chunk, index) = split(&point);
+        let cells = self
+            .chunks
+            .entry(chunk)
+            .or_insert_with(|| std::iter::repeat_with(|| None).take(CHUNK_CELLS).collect());
+        if cells[index].replace(tile).is_none() {
+            self.len += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.min, self.max)
+    }
+
+    /// Unlike `Map::iter`, points are yielded by value: slots hold only the
+    /// tile, and each point is rebuilt from its chunk and slot index.
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i32>, &Tile)> {
+        self.chunks.iter().flat_map(|(chunk, cells)| {
+            let corner = Point::new(chunk.x * CHUNK, chunk.y * CHUNK);
+            cells.iter().enumerate().filter_map(move |(i, cell)| {
+                let offset = Point::new(i as i32 % CHUNK, i as i32 / CHUNK);
+                cell.as_ref().map(|tile| (corner + offset, tile))
+            })
+        })
+    }
+}
+
+impl<Tile> Grid for ChunkMap<Tile> {
+    type Tile = Tile;
+
+    fn get(&self, point: &Point<i32>) -> Option<&Tile> {
+        ChunkMap::get(self, point)
+    }
+
+    fn rect(&self) -> Rect {
+        ChunkMap::rect(self)
+    }
+}
+
+impl<Tile> FromIterator<(Point<i32>, Tile)> for ChunkMap<Tile> {
+    fn from_iter<I: IntoIterator<Item = (Point<i32>, Tile)>>(iter: I) -> Self {
+        let mut map = ChunkMap::new();
+        for (point, tile) in iter {
+            map.insert(point, tile);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    #[test]
+    fn far_apart_cells() {
+        let mut map = ChunkMap::new();
+        map.insert(Point::new(-5_000_000, 3), 'a');
+        map.insert(Point::new(7_000_000, -17), 'b');
+        map.insert(Point::new(7_000_001, -17), 'c');
+        map.insert(Point::new(7_000_001, -17), 'd');
+        assert_eq!((3, 2), (map.len(), map.chunk_count()));
+        assert_eq!(Some(&'d'), map.get(&(7_000_001, -17).into()));
+        assert_eq!(None, map.get(&(0, 0).into()));
+        assert_eq!(
+            Rect::new((-5_000_000, -17).into(), (7_000_001, 3).into()),
+            map.rect()
+        );
+
+        let mut cells: Vec<_> = map.iter().map(|(p, c)| (p.x, *c)).collect();
+        cells.sort();
+        assert_eq!(
+            vec![(-5_000_000, 'a'), (7_000_000, 'b'), (7_000_001, 'd')],
+            cells
+        );
+        assert_eq!(
+            Some(((7_000_001, -17).into(), 1)),
+            map.cast((7_000_000, -17).into(), Direction::Right, |_| true)
+        );
+    }
+
+    #[test]
+    fn casting_across_chunks() {
+        let map: ChunkMap<char> = [((0, 0).into(), '.'), ((100, 0).into(), '#')]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            Some(((100, 0).into(), 100)),
+            map.cast((0, 0).into(), Direction::Right, |&c| c == '#')
+        );
+        assert_eq!(None, map.cast((0, 0).into(), Direction::Left, |_| true));
+    }
+}
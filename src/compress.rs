@@ -0,0 +1,170 @@
+use crate::{Map, Point};
+
+/// One compressed axis. Cell `i` covers the real coordinates
+/// `bounds[i]..bounds[i + 1]`: every input value gets a cell of its own,
+/// each gap between values collapses into a single cell, and there is one
+/// spare cell beyond the smallest and largest value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Axis {
+    bounds: Vec<i64>,
+}
+
+impl Axis {
+    pub fn new(values: impl IntoIterator<Item = i64>) -> Self {
+        let mut bounds: Vec<i64> = values.into_iter().flat_map(|v| [v, v + 1]).collect();
+        bounds.sort();
+        bounds.dedup();
+        if let (Some(&min), Some(&max)) = (bounds.first(), bounds.last()) {
+            bounds.insert(0, min - 1);
+            bounds.push(max + 1);
+        }
+        Axis { bounds }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len().saturating_sub(1)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn index(&self, value: i64) -> Option<usize> {
+        let i = self.bounds.partition_point(|&b| b <= value);
+        (i > 0 && i < self.bounds.len()).then(|| i - 1)
+    }
+
+    /// First real coordinate of cell `i`.
+    pub fn start(&self, i: usize) -> Option<i64> {
+        (i < self.len()).then(|| self.bounds[i])
+    }
+
+    /// How many real coordinates cell `i` stands for.
+    pub fn width(&self, i: usize) -> Option<u64> {
+        (i < self.len()).then(|| (self.bounds[i + 1] - self.bounds[i]) as u64)
+    }
+}
+
+/// Maps points with huge coordinates onto a small grid that keeps their
+/// order, so that a `Map` can be built over it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compression {
+    pub x: Axis,
+    pub y: Axis,
+}
+
+impl Compression {
+    pub fn new<'a>(points: impl IntoIterator<Item = &'a Point<i64>>) -> Self {
+        let (xs, ys): (Vec<i64>, Vec<i64>) = points.into_iter().map(|p| (p.x, p.y)).unzip();
+        Compression {
+            x: Axis::new(xs),
+            y: Axis::new(ys),
+        }
+    }
+
+    pub fn compress(&self, point: &Point<i64>) -> Option<Point<i32>> {
+        Some(Point::new(
+            self.x.index(point.x)? as i32,
+            self.y.index(point.y)? as i32,
+        ))
+    }
+
+    /// The real point at the top left of a compressed cell.
+    pub fn expand(&self, point: &Point<i32>) -> Option<Point<i64>> {
+        Some(Point::new(
+            self.x.start(usize::try_from(point.x).ok()?)?,
+            self.y.start(usize::try_from(point.y).ok()?)?,
+        ))
+    }
+
+    /// The number of real cells a compressed cell stands for, 0 outside.
+    pub fn weight(&self, point: &Point<i32>) -> u64 {
+        let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) else {
+            return 0;
+        };
+        self.x.width(x).unwrap_or(0) * self.y.width(y).unwrap_or(0)
+    }
+
+    /// Real area covered by a set of compressed cells.
+    pub fn area(&self, cells: impl IntoIterator<Item = Point<i32>>) -> u64 {
+        cells.into_iter().map(|p| self.weight(&p)).sum()
+    }
+
+    /// Compressed cells along the axis-aligned segment from `from` to `to`,
+    /// both ends included, or `None` if it is diagonal or leaves the axes.
+    pub fn segment(&self, from: &Point<i64>, to: &Point<i64>) -> Option<Vec<Point<i32>>> {
+        if from.x != to.x && from.y != to.y {
+            return None;
+        }
+        let (a, b) = (self.compress(from)?, self.compress(to)?);
+        let (min, max) = (a.min(&b), a.max(&b));
+        let cells = (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+            .collect();
+        Some(cells)
+    }
+
+    /// Builds the compressed map, asking `tile` for every cell.
+    pub fn map<Tile>(&self, mut tile: impl FnMut(Point<i32>) -> Tile) -> Map<Tile> {
+        let mut map = Map::new();
+        for y in 0..self.y.len() as i32 {
+            for x in 0..self.x.len() as i32 {
+                let point = Point::new(x, y);
+                map.insert(point, tile(point));
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn axis_cells() {
+        let axis = Axis::new([10, 3, 4, 10]);
+        // 2 | 3 | 4 | 5..10 | 10 | 11
+        assert_eq!(6, axis.len());
+        assert_eq!(Some(3), axis.index(7));
+        assert_eq!(Some(4), axis.index(10));
+        assert_eq!(None, axis.index(12));
+        assert_eq!(None, axis.index(1));
+        assert_eq!(Some(5), axis.width(3));
+        assert_eq!(Some(11), axis.start(5));
+        assert!(Axis::new([]).is_empty());
+    }
+
+    #[test]
+    fn exact_area_of_outline() {
+        let corners: Vec<Point<i64>> = [
+            (0, 0),
+            (1_000_000, 0),
+            (1_000_000, 500_000),
+            (400_000, 500_000),
+            (400_000, 2_000_000),
+            (0, 2_000_000),
+        ]
+        .into_iter()
+        .map(|(x, y)| Point::new(x, y))
+        .collect();
+        let compression = Compression::new(&corners);
+        let mut edge = HashSet::new();
+        for (i, from) in corners.iter().enumerate() {
+            let to = &corners[(i + 1) % corners.len()];
+            edge.extend(compression.segment(from, to).unwrap());
+        }
+        let map = compression.map(|p| edge.contains(&p));
+        assert!(map.len() < 100);
+
+        let outside = map.flood_fill((0, 0).into(), |_, &wall| !wall);
+        let total = compression.area(map.iter().map(|(p, _)| *p));
+        let inside = total - compression.area(outside);
+        let expected = 1_000_001 * 500_001 + 400_001 * 2_000_001 - 400_001 * 500_001;
+        assert_eq!(expected, inside);
+
+        let corner = compression.compress(&corners[3]).unwrap();
+        assert_eq!(Some(corners[3]), compression.expand(&corner));
+        assert_eq!(1, compression.weight(&corner));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::playback::{Command, Entity, Recorder};
+use crate::states::StateSet;
+use crate::walker::Guard;
+use crate::{Direction, Map, Point};
+use anyhow::Result;
+use std::io::{BufRead, Write};
+
+/// A walker state with a place on the map and a facing.
+pub trait Heading {
+    fn pos(&self) -> Point<i32>;
+    fn dir(&self) -> Direction;
+}
+
+impl Heading for (Point<i32>, Direction) {
+    fn pos(&self) -> Point<i32> {
+        self.0
+    }
+    fn dir(&self) -> Direction {
+        self.1
+    }
+}
+
+impl Heading for Guard {
+    fn pos(&self) -> Point<i32> {
+        self.pos
+    }
+    fn dir(&self) -> Direction {
+        self.dir
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    At(Point<i32>),
+    State(Point<i32>, Direction),
+}
+
+/// Why stepping stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    /// The walker has no next state.
+    Finished,
+    /// The new state was already visited.
+    Loop,
+}
+
+fn arrow(dir: Direction) -> char {
+    match dir {
+        Direction::Up => '^',
+        Direction::Right => '>',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+    }
+}
+
+/// Steps a walker over a map one state at a time, taking the same terminal
+/// commands as `playback::Player`. Every state is kept, so rewinding is just
+/// moving back through the history, and stepping forward again replays it
+/// rather than calling `step`. Each new state is also recorded as a
+/// playback frame, see `recording`.
+pub struct Debugger<'a, Tile, S, F, G> {
+    map: &'a Map<Tile>,
+    step: F,
+    glyph: G,
+    history: Vec<S>,
+    recording: Recorder<Tile>,
+    cursor: usize,
+    finished: bool,
+    seen: StateSet,
+    breakpoints: Vec<Breakpoint>,
+    radius: i32,
+}
+
+impl<'a, Tile, S, F, G> Debugger<'a, Tile, S, F, G>
+where
+    Tile: Clone + PartialEq,
+    S: Heading + Clone,
+    F: FnMut(&S) -> Option<S>,
+    G: Fn(&Tile) -> char,
+{
+    pub fn new(map: &'a Map<Tile>, start: S, step: F, glyph: G) -> Self {
+        let mut seen = StateSet::for_map(map);
+        seen.insert(start.pos(), start.dir());
+        let mut recording = Recorder::new(map);
+        recording.delta(Vec::new(), vec![Self::entity(&start)]);
+        Debugger {
+            map,
+            step,
+            glyph,
+            history: vec![start],
+            recording,
+            cursor: 0,
+            finished: false,
+            seen,
+            breakpoints: Vec::new(),
+            radius: 5,
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.history[self.cursor]
+    }
+    /// Number of steps taken to reach the current state.
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+    /// States visited up to and including the current one.
+    pub fn seen(&self) -> &StateSet {
+        &self.seen
+    }
+
+    /// Every state reached so far as a frame with the walker drawn as an
+    /// arrow, to replay with `Player` or dump with `write_text`.
+    pub fn recording(&self) -> &Recorder<Tile> {
+        &self.recording
+    }
+
+    fn entity(state: &S) -> Entity {
+        Entity {
+            pos: state.pos(),
+            glyph: arrow(state.dir()),
+        }
+    }
+
+    pub fn breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn step(&mut self) -> Option<Stop> {
+        if self.cursor + 1 == self.history.len() {
+            if self.finished {
+                return Some(Stop::Finished);
+            }
+            match (self.step)(&self.history[self.cursor]) {
+                Some(next) => {
+                    self.recording.delta(Vec::new(), vec![Self::entity(&next)]);
+                    self.history.push(next);
+                }
+                None => {
+                    self.finished = true;
+                    return Some(Stop::Finished);
+                }
+            }
+        }
+        self.cursor += 1;
+        let (pos, dir) = (self.state().pos(), self.state().dir());
+        if !self.seen.insert(pos, dir) {
+            return Some(Stop::Loop);
+        }
+        self.breakpoints
+            .iter()
+            .find(|b| match b {
+                Breakpoint::At(at) => *at == pos,
+                Breakpoint::State(at, facing) => (*at, *facing) == (pos, dir),
+            })
+            .map(|b| Stop::Breakpoint(*b))
+    }
+
+    /// Steps until something stops it.
+    pub fn run(&mut self) -> Stop {
+        loop {
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+        }
+    }
+
+    /// Rewinds to step `target`, or steps forward to it, stopping early if
+    /// something stops the walker on the way.
+    pub fn jump(&mut self, target: usize) -> Option<Stop> {
+        if target <= self.cursor {
+            self.rewind(self.cursor - target);
+            return None;
+        }
+        (self.cursor..target).find_map(|_| self.step())
+    }
+
+    pub fn rewind(&mut self, steps: usize) {
+        self.cursor = self.cursor.saturating_sub(steps);
+        self.seen.clear();
+        for state in &self.history[..=self.cursor] {
+            self.seen.insert(state.pos(), state.dir());
+        }
+    }
+
+    /// The map around the walker, which is drawn as an arrow.
+    pub fn window(&self) -> String {
+        let centre = self.state().pos();
+        let mut result = String::new();
+        for y in centre.y - self.radius..=centre.y + self.radius {
+            for x in centre.x - self.radius..=centre.x + self.radius {
+                let point = Point { x, y };
+                let c = if point == centre {
+                    arrow(self.state().dir())
+                } else {
+                    self.map.get(&point).map_or(' ', &self.glyph)
+                };
+                result.push(c);
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    pub fn draw(&self, out: &mut impl Write) -> Result<()> {
+        let pos = self.state().pos();
+        write!(out, "{}", self.window())?;
+        writeln!(
+            out,
+            "step {}  at {} facing {:?}",
+            self.cursor,
+            pos,
+            self.state().dir()
+        )?;
+        let here: Vec<Direction> = self.seen.directions(&pos).collect();
+        writeln!(out, "seen here: {:?}", here)?;
+        Ok(())
+    }
+
+    /// Reads one command per line from `input` until `q` or end of input.
+    pub fn interactive(&mut self, input: impl BufRead, out: &mut impl Write) -> Result<()> {
+        self.draw(out)?;
+        for line in input.lines() {
+            let stop = match Command::parse(&line?) {
+                Some(Command::Next(n) | Command::Run(Some(n))) => (0..n).find_map(|_| self.step()),
+                Some(Command::Run(None)) => Some(self.run()),
+                Some(Command::Previous(n)) => {
+                    self.rewind(n);
+                    None
+                }
+                Some(Command::Jump(target)) => self.jump(target),
+                Some(Command::Break(at, dir)) => {
+                    self.breakpoint(match dir {
+                        Some(dir) => Breakpoint::State(at, dir),
+                        None => Breakpoint::At(at),
+                    });
+                    None
+                }
+                Some(Command::ClearBreakpoints) => {
+                    self.breakpoints.clear();
+                    None
+                }
+                Some(Command::Window(radius)) => {
+                    self.radius = radius;
+                    None
+                }
+                Some(Command::Quit) => break,
+                _ => {
+                    writeln!(
+                        out,
+                        "commands: n [steps], p [steps], j <step>, r [steps], \
+                         b <x> <y> [U|R|D|L], d, w <radius>, q"
+                    )?;
+                    continue;
+                }
+            };
+            if let Some(stop) = stop {
+                writeln!(out, "stopped: {:?}", stop)?;
+            }
+            self.draw(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walk(
+        map: &Map<char>,
+    ) -> impl FnMut(&(Point<i32>, Direction)) -> Option<(Point<i32>, Direction)> + '_ {
+        |&(pos, dir)| match map.get(&(pos + dir))? {
+            '#' => Some((pos, dir.turn_right())),
+            _ => Some((pos + dir, dir)),
+        }
+    }
+
+    #[test]
+    fn steps_and_rewinds() {
+        let map: Map<char> = Map::parse("..#.\n...#\n....\n.#..".as_bytes()).unwrap();
+        let start = ((0, 1).into(), Direction::Right);
+        let mut debugger = Debugger::new(&map, start, walk(&map), |&c| c);
+        debugger.breakpoint(Breakpoint::At((2, 2).into()));
+        assert_eq!(
+            Stop::Breakpoint(Breakpoint::At((2, 2).into())),
+            debugger.run()
+        );
+        assert_eq!(4, debugger.position());
+        assert_eq!(Stop::Finished, debugger.run());
+        debugger.rewind(100);
+        assert_eq!(start, *debugger.state());
+        assert_eq!(1, debugger.seen().len());
+
+        let mut out = Vec::new();
+        debugger
+            .interactive("w 1\nb 2 1 D\nr\n?\np 2\nq\nn\n".as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(1, debugger.position());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("stopped: Breakpoint(State(Point { x: 2, y: 1 }, Down))"));
+        // Turned in place, so both facings have been seen on this cell.
+        assert!(out
+            .contains(".#.\n.v#\n...\nstep 3  at (2, 1) facing Down\nseen here: [Right, Down]\n"));
+        assert!(out.contains("commands:"));
+
+        assert_eq!(
+            Some(Stop::Breakpoint(Breakpoint::State(
+                (2, 1).into(),
+                Direction::Down
+            ))),
+            debugger.jump(3)
+        );
+        assert_eq!((None, 0), (debugger.jump(0), debugger.position()));
+        assert_eq!(
+            Some("..#.\n>..#\n....\n.#..\n".to_string()),
+            debugger.recording().render(0, |&c| c)
+        );
+    }
+}
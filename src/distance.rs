@@ -0,0 +1,165 @@
+use crate::{Map, Point};
+use dary_heap::QuaternaryHeap;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut {
+    pub from: Point<i32>,
+    pub to: Point<i32>,
+    pub saving: usize,
+}
+
+impl<Tile> Map<Tile> {
+    fn empty_field(&self) -> Map<Option<usize>> {
+        let mut field = Map::new();
+        for point in self.map.keys() {
+            field.insert(*point, None);
+        }
+        field.min = self.min;
+        field.max = self.max;
+        field
+    }
+
+    /// Steps from every cell to the nearest of `sources`, `None` where no
+    /// source can be reached. The result covers the same cells as `self`.
+    pub fn distance_field(
+        &self,
+        sources: impl IntoIterator<Item = Point<i32>>,
+        passable: impl Fn(&Tile) -> bool,
+    ) -> Map<Option<usize>> {
+        let mut field = self.empty_field();
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if let Some(distance @ None) = field.map.get_mut(&source) {
+                *distance = Some(0);
+                queue.push_back((source, 0));
+            }
+        }
+        while let Some((point, distance)) = queue.pop_front() {
+            for (next, tile) in self.neighbors(point) {
+                let seen = field.map.get_mut(&next).unwrap();
+                if seen.is_none() && passable(tile) {
+                    *seen = Some(distance + 1);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        field
+    }
+
+    /// Like `distance_field`, with `cost` giving the price of stepping onto a
+    /// tile or `None` if it can't be entered.
+    pub fn weighted_distance_field(
+        &self,
+        sources: impl IntoIterator<Item = Point<i32>>,
+        cost: impl Fn(&Tile) -> Option<usize>,
+    ) -> Map<Option<usize>> {
+        let mut field = self.empty_field();
+        let mut heap = QuaternaryHeap::new();
+        for source in sources {
+            if let Some(distance) = field.map.get_mut(&source) {
+                *distance = Some(0);
+                heap.push(Reverse((0, source.x, source.y)));
+            }
+        }
+        while let Some(Reverse((distance, x, y))) = heap.pop() {
+            let point = Point { x, y };
+            if field.map[&point].is_some_and(|best| best < distance) {
+                continue;
+            }
+            for (next, tile) in self.neighbors(point) {
+                let Some(step) = cost(tile) else { continue };
+                let best = field.map.get_mut(&next).unwrap();
+                if best.map_or(true, |best| distance + step < best) {
+                    *best = Some(distance + step);
+                    heap.push(Reverse((distance + step, next.x, next.y)));
+                }
+            }
+        }
+        field
+    }
+}
+
+/// Every pair of reachable cells at most `radius` apart (Manhattan) where
+/// jumping straight from one to the other shortens the best start-to-end
+/// route by at least `min_saving`.
+pub fn shortcuts(
+    from_start: &Map<Option<usize>>,
+    to_end: &Map<Option<usize>>,
+    radius: usize,
+    min_saving: usize,
+) -> Vec<Shortcut> {
+    let best = from_start
+        .iter()
+        .filter_map(|(point, from)| Some((*from)? + to_end.get(point).copied().flatten()?))
+        .min();
+    let Some(best) = best else { return Vec::new() };
+
+    let radius = radius as i32;
+    let mut found = Vec::new();
+    for (&from, distance) in from_start.iter() {
+        let Some(distance) = *distance else { continue };
+        for dy in -radius..=radius {
+            let span = radius - dy.abs();
+            for dx in -span..=span {
+                let to = from + Point { x: dx, y: dy };
+                let Some(Some(remaining)) = to_end.get(&to) else {
+                    continue;
+                };
+                let length = distance + from.manhattan(&to) + remaining;
+                if length + min_saving <= best {
+                    found.push(Shortcut {
+                        from,
+                        to,
+                        saving: best - length,
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RACE: &str = "\
+###############
+#...#...#.....#
+#.#.#.#.#.###.#
+#S#...#.#.#...#
+#######.#.#.###
+#######.#.#...#
+#######.#.###.#
+###..E#...#...#
+###.#######.###
+#...###...#...#
+#.#####.#.###.#
+#.#...#.#.#...#
+#.#.#.#.#.#.###
+#...#...#...###
+###############";
+
+    #[test]
+    fn race_shortcuts() {
+        let map: Map<char> = Map::parse(RACE.as_bytes()).unwrap();
+        let find = |c| map.iter().find(|(_, t)| **t == c).map(|(p, _)| *p).unwrap();
+        let (start, end) = (find('S'), find('E'));
+        let from_start = map.distance_field([start], |&c| c != '#');
+        let to_end = map.distance_field([end], |&c| c != '#');
+        assert_eq!(Some(84), *from_start.get(&end).unwrap());
+        assert_eq!(None, *from_start.get(&Point::new(0, 0)).unwrap());
+        assert_eq!(from_start.rect(), map.rect());
+
+        let weighted = map.weighted_distance_field([start], |&c| (c != '#').then_some(1));
+        assert!(map
+            .iter()
+            .all(|(p, _)| weighted.get(p) == from_start.get(p)));
+
+        assert_eq!(5, shortcuts(&from_start, &to_end, 2, 20).len());
+        assert_eq!(1, shortcuts(&from_start, &to_end, 2, 64).len());
+        assert_eq!(7, shortcuts(&from_start, &to_end, 20, 74).len());
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{Map, Point, Rect};
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const RED: Rgb = Rgb(220, 40, 40);
+    pub const GREEN: Rgb = Rgb(40, 180, 70);
+    pub const BLUE: Rgb = Rgb(50, 90, 220);
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Overlay {
+    /// A line through the centres of the cells, in order.
+    Path { points: Vec<Point<i32>>, color: Rgb },
+    /// Cells filled with a single colour.
+    Points { points: Vec<Point<i32>>, color: Rgb },
+}
+
+/// Writes maps as images under `dir`, each cell drawn as a `scale` pixel
+/// square with the overlays painted on top in the order they were added.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    pub dir: PathBuf,
+    pub scale: usize,
+    overlays: Vec<Overlay>,
+}
+
+impl Exporter {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Exporter {
+            dir: dir.into(),
+            scale: 4,
+            overlays: Vec::new(),
+        }
+    }
+
+    pub fn scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    fn write(&self, file: String, data: &[u8]) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(file);
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    pub fn ppm<Tile>(
+        &self,
+        name: &str,
+        map: &Map<Tile>,
+        color: impl Fn(&Tile) -> Rgb,
+    ) -> Result<PathBuf> {
+        let image = self.raster(map, color);
+        let mut data = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+        data.extend(image.pixels.iter().flat_map(|p| [p.0, p.1, p.2]));
+        self.write(format!("{name}.ppm"), &data)
+    }
+
+    pub fn png<Tile>(
+        &self,
+        name: &str,
+        map: &Map<Tile>,
+        color: impl Fn(&Tile) -> Rgb,
+    ) -> Result<PathBuf> {
+        let image = self.raster(map, color);
+        self.write(format!("{name}.png"), &image.png())
+    }
+
+    pub fn svg<Tile>(
+        &self,
+        name: &str,
+        map: &Map<Tile>,
+        color: impl Fn(&Tile) -> Rgb,
+    ) -> Result<PathBuf> {
+        let rect = map.rect();
+        let s = self.scale;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" shape-rendering=\"crispEdges\">\n",
+            rect.width() * s,
+            rect.height() * s
+        );
+        let corner = |p: &Point<i32>| {
+            (
+                (p.x - rect.min.x) as usize * s,
+                (p.y - rect.min.y) as usize * s,
+            )
+        };
+        let cell = |svg: &mut String, p: &Point<i32>, color: Rgb| {
+            let (x, y) = corner(p);
+            writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{s}\" height=\"{s}\" fill=\"{}\"/>",
+                color.hex()
+            )
+            .unwrap();
+        };
+        for point in rect.points() {
+            if let Some(tile) = map.get(&point) {
+                cell(&mut svg, &point, color(tile));
+            }
+        }
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Points { points, color } => {
+                    points.iter().for_each(|p| cell(&mut svg, p, *color))
+                }
+                Overlay::Path { points, color } => {
+                    let coords: Vec<String> = points
+                        .iter()
+                        .map(|p| {
+                            let (x, y) = corner(p);
+                            format!("{},{}", x + s / 2, y + s / 2)
+                        })
+                        .collect();
+                    writeln!(
+                        svg,
+                        "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                        coords.join(" "),
+                        color.hex(),
+                        (s / 4).max(1)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        self.write(format!("{name}.svg"), svg.as_bytes())
+    }
+
+    fn raster<Tile>(&self, map: &Map<Tile>, color: impl Fn(&Tile) -> Rgb) -> Image {
+        let rect = map.rect();
+        let mut image = Image::new(rect, self.scale);
+        for point in rect.points() {
+            if let Some(tile) = map.get(&point) {
+                image.fill_cell(&point, color(tile));
+            }
+        }
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Points { points, color } => {
+                    points.iter().for_each(|p| image.fill_cell(p, *color))
+                }
+                Overlay::Path { points, color } => {
+                    for pair in points.windows(2) {
+                        image.line(&pair[0], &pair[1], *color);
+                    }
+                    if let [only] = points.as_slice() {
+                        image.line(only, only, *color);
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+struct Image {
+    rect: Rect,
+    scale: usize,
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn new(rect: Rect, scale: usize) -> Self {
+        let (width, height) = (rect.width() * scale, rect.height() * scale);
+        Image {
+            rect,
+            scale,
+            width,
+            height,
+            pixels: vec![Rgb::BLACK; width * height],
+        }
+    }
+
+    fn pixel(&mut self, x: i64, y: i64, color: Rgb) {
+        if (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y) {
+            self.pixels[y as usize * self.width + x as usize] = color;
+        }
+    }
+
+    fn centre(&self, point: &Point<i32>) -> (i64, i64) {
+        let s = self.scale as i64;
+        (
+            (point.x - self.rect.min.x) as i64 * s + s / 2,
+            (point.y - self.rect.min.y) as i64 * s + s / 2,
+        )
+    }
+
+    fn fill_cell(&mut self, point: &Point<i32>, color: Rgb) {
+        let s = self.scale as i64;
+        let (cx, cy) = self.centre(point);
+        for y in cy - s / 2..cy - s / 2 + s {
+            for x in cx - s / 2..cx - s / 2 + s {
+                self.pixel(x, y, color);
+            }
+        }
+    }
+
+    // Bresenham between cell centres.
+    fn line(&mut self, from: &Point<i32>, to: &Point<i32>, color: Rgb) {
+        let ((mut x, mut y), (x1, y1)) = (self.centre(from), self.centre(to));
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+        loop {
+            self.pixel(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // 8-bit RGB, unfiltered rows in stored (uncompressed) deflate blocks.
+    fn png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flat_map(|p| [p.0, p.1, p.2]));
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xffff).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            zlib.push(blocks.peek().is_none() as u8);
+            let len = block.len() as u16;
+            zlib.extend(len.to_le_bytes());
+            zlib.extend((!len).to_le_bytes());
+            zlib.extend(block);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        for (kind, data) in [(b"IHDR", header), (b"IDAT", zlib), (b"IEND", Vec::new())] {
+            png.extend((data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend(kind);
+            png.extend(&data);
+            let crc = crc32(&png[start..]);
+            png.extend(crc.to_be_bytes());
+        }
+        png
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn writes_images() {
+        let map: Map<char> = Map::parse("#..\n.#.".as_bytes()).unwrap();
+        let dir = std::env::temp_dir().join("adv-code-2024-export");
+        let exporter = Exporter::new(&dir)
+            .scale(2)
+            .overlay(Overlay::Path {
+                points: vec![(0, 1).into(), (2, 1).into()],
+                color: Rgb::RED,
+            })
+            .overlay(Overlay::Points {
+                points: vec![(2, 0).into()],
+                color: Rgb::GREEN,
+            });
+        let color = |&c: &char| if c == '#' { Rgb::WHITE } else { Rgb::BLACK };
+
+        let ppm = fs::read(exporter.ppm("map", &map, color).unwrap()).unwrap();
+        assert!(ppm.starts_with(b"P6\n6 4\n255\n"));
+        assert_eq!(11 + 6 * 4 * 3, ppm.len());
+        // Top-left pixel is a wall, the top-right cell is a highlighted point.
+        assert_eq!([255, 255, 255], ppm[11..14]);
+        assert_eq!([40, 180, 70], ppm[11 + 5 * 3..11 + 6 * 3]);
+
+        let png = fs::read(exporter.png("map", &map, color).unwrap()).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x06\0\0\0\x04"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        let svg = fs::read_to_string(exporter.svg("map", &map, color).unwrap()).unwrap();
+        assert!(svg.contains("<polyline points=\"1,3 5,3\""));
+        assert_eq!(7, svg.matches("<rect").count());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::search::{dijkstra, SearchResult};
+use crate::{Direction, Map, Point};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    /// Nodes reachable in one step from `node`, with the cost of the step.
+    fn neighbors(&self, node: &Self::Node) -> impl Iterator<Item = (Self::Node, usize)>;
+}
+
+/// An explicit adjacency list where every edge costs 1.
+impl<N> Graph for HashMap<N, Vec<N>>
+where
+    N: Clone + Eq + Hash,
+{
+    type Node = N;
+
+    fn neighbors(&self, node: &N) -> impl Iterator<Item = (N, usize)> {
+        self.get(node)
+            .into_iter()
+            .flatten()
+            .map(|next| (next.clone(), 1))
+    }
+}
+
+/// Positions of a map as nodes, joined orthogonally wherever
+/// `rule(from, to)` gives a cost.
+pub struct MapGraph<'a, Tile, F> {
+    map: &'a Map<Tile>,
+    rule: F,
+}
+
+impl<'a, Tile, F> Graph for MapGraph<'a, Tile, F>
+where
+    F: Fn(&Tile, &Tile) -> Option<usize>,
+{
+    type Node = Point<i32>;
+
+    fn neighbors(&self, node: &Point<i32>) -> impl Iterator<Item = (Point<i32>, usize)> {
+        let from = self.map.get(node);
+        self.map
+            .neighbors(*node)
+            .filter_map(move |(next, tile)| Some((next, (self.rule)(from?, tile)?)))
+    }
+}
+
+/// Positions with a facing as nodes: step forward when `step` gives a cost
+/// for the tile ahead, or turn in place for `turn` if turning is allowed.
+pub struct HeadingGraph<'a, Tile, F> {
+    map: &'a Map<Tile>,
+    step: F,
+    turn: Option<usize>,
+}
+
+impl<'a, Tile, F> Graph for HeadingGraph<'a, Tile, F>
+where
+    F: Fn(&Tile) -> Option<usize>,
+{
+    type Node = (Point<i32>, Direction);
+
+    fn neighbors(&self, &(pos, dir): &Self::Node) -> impl Iterator<Item = (Self::Node, usize)> {
+        let ahead = pos + dir;
+        let forward = self
+            .map
+            .get(&ahead)
+            .and_then(|tile| (self.step)(tile))
+            .map(|cost| ((ahead, dir), cost));
+        let turns = self.turn.into_iter().flat_map(move |cost| {
+            [
+                ((pos, dir.turn_left()), cost),
+                ((pos, dir.turn_right()), cost),
+            ]
+        });
+        forward.into_iter().chain(turns)
+    }
+}
+
+impl<Tile> Map<Tile> {
+    pub fn graph<F>(&self, rule: F) -> MapGraph<'_, Tile, F>
+    where
+        F: Fn(&Tile, &Tile) -> Option<usize>,
+    {
+        MapGraph { map: self, rule }
+    }
+
+    pub fn heading_graph<F>(&self, step: F, turn: Option<usize>) -> HeadingGraph<'_, Tile, F>
+    where
+        F: Fn(&Tile) -> Option<usize>,
+    {
+        HeadingGraph {
+            map: self,
+            step,
+            turn,
+        }
+    }
+}
+
+/// Number of edges on the fewest-edge route from `start` to every reachable node.
+pub fn bfs<G: Graph>(graph: &G, start: G::Node) -> HashMap<G::Node, usize> {
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[&node];
+        for (next, _) in graph.neighbors(&node) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Reachable nodes in depth-first preorder.
+pub fn dfs<G: Graph>(graph: &G, start: G::Node) -> Vec<G::Node> {
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        if !seen.insert(node.clone()) {
+            continue;
+        }
+        let mut next: Vec<_> = graph.neighbors(&node).map(|(n, _)| n).collect();
+        next.reverse();
+        stack.extend(next.into_iter().filter(|n| !seen.contains(n)));
+        order.push(node);
+    }
+    order
+}
+
+pub fn shortest_path<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    goal: impl FnMut(&G::Node) -> bool,
+) -> Option<SearchResult<G::Node>> {
+    dijkstra(
+        start,
+        |node| graph.neighbors(node).collect::<Vec<_>>(),
+        goal,
+    )
+}
+
+/// Every node reachable from `roots`, each before all the nodes it points
+/// to, or `None` if they contain a cycle.
+pub fn topological_sort<G: Graph>(
+    graph: &G,
+    roots: impl IntoIterator<Item = G::Node>,
+) -> Option<Vec<G::Node>> {
+    let mut done = HashSet::new();
+    let mut active = HashSet::new();
+    let mut order = Vec::new();
+    for root in roots {
+        if done.contains(&root) {
+            continue;
+        }
+        active.insert(root.clone());
+        let mut stack = vec![(
+            root.clone(),
+            graph.neighbors(&root).map(|(n, _)| n).collect::<Vec<_>>(),
+        )];
+        while let Some((node, next)) = stack.last_mut() {
+            match next.pop() {
+                Some(next) if active.contains(&next) => return None,
+                Some(next) if done.contains(&next) => {}
+                Some(next) => {
+                    active.insert(next.clone());
+                    let after = graph.neighbors(&next).map(|(n, _)| n).collect();
+                    stack.push((next, after));
+                }
+                None => {
+                    active.remove(node);
+                    done.insert(node.clone());
+                    order.push(node.clone());
+                    stack.pop();
+                }
+            }
+        }
+    }
+    order.reverse();
+    Some(order)
+}
+
+struct Tarjan<'g, G: Graph> {
+    graph: &'g G,
+    index: HashMap<G::Node, usize>,
+    low: HashMap<G::Node, usize>,
+    on_stack: HashSet<G::Node>,
+    stack: Vec<G::Node>,
+    calls: Vec<(G::Node, Vec<G::Node>)>,
+}
+
+impl<'g, G: Graph> Tarjan<'g, G> {
+    fn visit(&mut self, node: G::Node) {
+        let n = self.index.len();
+        self.index.insert(node.clone(), n);
+        self.low.insert(node.clone(), n);
+        self.on_stack.insert(node.clone());
+        self.stack.push(node.clone());
+        let next = self.graph.neighbors(&node).map(|(n, _)| n).collect();
+        self.calls.push((node, next));
+    }
+
+    fn lower(&mut self, node: &G::Node, reach: usize) {
+        let low = self.low.get_mut(node).unwrap();
+        *low = (*low).min(reach);
+    }
+}
+
+/// Strongly connected components reachable from `roots` (Tarjan), each
+/// listed after every component it can reach.
+pub fn strongly_connected<G: Graph>(
+    graph: &G,
+    roots: impl IntoIterator<Item = G::Node>,
+) -> Vec<Vec<G::Node>> {
+    let mut tarjan = Tarjan {
+        graph,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        on_stack: HashSet::new(),
+        stack: Vec::new(),
+        calls: Vec::new(),
+    };
+    let mut components = Vec::new();
+
+    for root in roots {
+        if tarjan.index.contains_key(&root) {
+            continue;
+        }
+        tarjan.visit(root);
+        while let Some((node, next)) = tarjan.calls.last_mut() {
+            let node = node.clone();
+            if let Some(next) = next.pop() {
+                if !tarjan.index.contains_key(&next) {
+                    tarjan.visit(next);
+                } else if tarjan.on_stack.contains(&next) {
+                    let reach = tarjan.index[&next];
+                    tarjan.lower(&node, reach);
+                }
+                continue;
+            }
+            tarjan.calls.pop();
+            if tarjan.low[&node] == tarjan.index[&node] {
+                let mut component = Vec::new();
+                while let Some(member) = tarjan.stack.pop() {
+                    tarjan.on_stack.remove(&member);
+                    let last = member == node;
+                    component.push(member);
+                    if last {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some((parent, _)) = tarjan.calls.last() {
+                let (parent, reach) = (parent.clone(), tarjan.low[&node]);
+                tarjan.lower(&parent, reach);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_graph() {
+        let graph: HashMap<u32, Vec<u32>> = HashMap::from([
+            (1, vec![2, 3]),
+            (2, vec![4]),
+            (3, vec![4]),
+            (4, vec![5]),
+            (5, vec![4, 6]),
+        ]);
+        assert_eq!(Some(&3), bfs(&graph, 1).get(&5));
+        assert_eq!(vec![1, 2, 4, 5, 6, 3], dfs(&graph, 1));
+        assert_eq!(None, topological_sort(&graph, [1]));
+
+        let mut components = strongly_connected(&graph, [1]);
+        components.iter_mut().for_each(|c| c.sort());
+        assert_eq!(
+            vec![vec![6], vec![4, 5], vec![3], vec![2], vec![1]],
+            components
+        );
+
+        let dag: HashMap<u32, Vec<u32>> =
+            HashMap::from([(3, vec![1]), (2, vec![3, 1]), (4, vec![2])]);
+        assert_eq!(Some(vec![4, 2, 3, 1]), topological_sort(&dag, [1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn map_graphs() {
+        let map: Map<char> = Map::parse("S.#\n..#\n#..".as_bytes()).unwrap();
+        let open = map.graph(|_, &to| (to != '#').then_some(1));
+        assert_eq!(Some(&4), bfs(&open, (0, 0).into()).get(&(2, 2).into()));
+        assert_eq!(6, dfs(&open, (0, 0).into()).len());
+
+        let heading = map.heading_graph(|&c| (c != '#').then_some(1), Some(1000));
+        let found = shortest_path(&heading, ((0, 0).into(), Direction::Right), |(p, _)| {
+            *p == (2, 2).into()
+        })
+        .unwrap();
+        assert_eq!(2004, found.distance);
+        let sizes: Vec<usize> = strongly_connected(&open, [(0, 0).into()])
+            .iter()
+            .map(Vec::len)
+            .collect();
+        assert_eq!(vec![6], sizes);
+    }
+}
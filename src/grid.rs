@@ -0,0 +1,208 @@
+use crate::{Map, Point, Rect};
+use std::collections::HashSet;
+
+/// Read access shared by the grid backends, with line-of-sight queries built
+/// on top of it.
+pub trait Grid {
+    type Tile;
+
+    fn get(&self, point: &Point<i32>) -> Option<&Self::Tile>;
+
+    /// Bounds of every cell `get` can return.
+    fn rect(&self) -> Rect;
+
+    /// Cells from the one after `from` onwards in steps of `direction`,
+    /// skipping empty cells and ending at the edge of the grid.
+    fn line(&self, from: Point<i32>, direction: impl Into<Point<i32>>) -> Line<'_, Self> {
+        let step = direction.into();
+        assert!(step != Point::new(0, 0), "a line needs a non-zero step");
+        Line {
+            grid: self,
+            rect: self.rect(),
+            next: from + step,
+            step,
+        }
+    }
+
+    /// The first cell along the line that satisfies `stop` and how many
+    /// steps away it is, or `None` if the line leaves the grid first.
+    fn cast(
+        &self,
+        from: Point<i32>,
+        direction: impl Into<Point<i32>>,
+        mut stop: impl FnMut(&Self::Tile) -> bool,
+    ) -> Option<(Point<i32>, usize)> {
+        let step = direction.into();
+        let (point, _) = self.line(from, step).find(|(_, tile)| stop(tile))?;
+        let steps = match step.x {
+            0 => (point.y - from.y) / step.y,
+            x => (point.x - from.x) / x,
+        };
+        Some((point, steps as usize))
+    }
+
+    /// Every cell in line of sight of `from`: none of the cells strictly
+    /// between them on the straight (Bresenham) line satisfies `blocks`.
+    /// Cells that block can still be seen themselves, empty cells never
+    /// block, and `from` is left out.
+    fn visible(
+        &self,
+        from: Point<i32>,
+        mut blocks: impl FnMut(&Self::Tile) -> bool,
+    ) -> HashSet<Point<i32>> {
+        self.rect()
+            .points()
+            .filter(|to| *to != from && self.get(to).is_some())
+            .filter(|&to| {
+                let line = bresenham(from, to);
+                line[1..line.len() - 1]
+                    .iter()
+                    .all(|p| !self.get(p).is_some_and(&mut blocks))
+            })
+            .collect()
+    }
+
+    /// Cells seen from `from` along the eight compass rays only, a cheaper
+    /// stand-in for `visible` when only straight and diagonal sight lines
+    /// matter. A ray ends at the first cell that `blocks` it, which is
+    /// itself visible.
+    fn visible_along_compass(
+        &self,
+        from: Point<i32>,
+        mut blocks: impl FnMut(&Self::Tile) -> bool,
+    ) -> HashSet<Point<i32>> {
+        let mut seen = HashSet::new();
+        for step in Point::new(0, 0).neighbors8() {
+            for (point, tile) in self.line(from, step) {
+                seen.insert(point);
+                if blocks(tile) {
+                    break;
+                }
+            }
+        }
+        seen
+    }
+}
+
+// Cells on the straight line from `from` to `to`, both ends included.
+fn bresenham(from: Point<i32>, to: Point<i32>) -> Vec<Point<i32>> {
+    let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let (sx, sy) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut err = dx + dy;
+    let mut point = from;
+    let mut line = vec![point];
+    while point != to {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            point.x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            point.y += sy;
+        }
+        line.push(point);
+    }
+    line
+}
+
+pub struct Line<'a, G: ?Sized> {
+    grid: &'a G,
+    rect: Rect,
+    next: Point<i32>,
+    step: Point<i32>,
+}
+
+impl<'a, G: Grid + ?Sized> Iterator for Line<'a, G> {
+    type Item = (Point<i32>, &'a G::Tile);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.rect.contains(&self.next) {
+            let point = self.next;
+            self.next = point + self.step;
+            if let Some(tile) = self.grid.get(&point) {
+                return Some((point, tile));
+            }
+        }
+        None
+    }
+}
+
+impl<Tile> Grid for Map<Tile> {
+    type Tile = Tile;
+
+    fn get(&self, point: &Point<i32>) -> Option<&Tile> {
+        self.map.get(point)
+    }
+
+    fn rect(&self) -> Rect {
+        Map::rect(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    const ROOM: &str = "\
+#.....
+..#...
+......
+.^..#.";
+
+    #[test]
+    fn casting() {
+        let map: Map<char> = Map::parse(ROOM.as_bytes()).unwrap();
+        let guard = Point::new(1, 3);
+        assert_eq!(None, map.cast(guard, Direction::Up, |&c| c == '#'));
+        assert_eq!(
+            Some(((4, 3).into(), 3)),
+            map.cast(guard, Direction::Right, |&c| c == '#')
+        );
+        assert_eq!(
+            Some(((2, 1).into(), 2)),
+            map.cast(Point::new(0, 3), Point::new(1, -1), |&c| c == '#')
+        );
+
+        let line: Vec<_> = map.line(guard, Direction::Left).collect();
+        assert_eq!(vec![(Point::new(0, 3), &'.')], line);
+
+        let seen = map.visible_along_compass(Point::new(2, 2), |&c| c == '#');
+        assert!(seen.contains(&(2, 1).into()) && !seen.contains(&(2, 0).into()));
+        assert!(seen.contains(&(0, 0).into()) && seen.contains(&(5, 2).into()));
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let map: Map<char> = Map::parse(ROOM.as_bytes()).unwrap();
+        let seen = map.visible(Point::new(2, 2), |&c| c == '#');
+        // Off the compass rays, which visible_along_compass never sees.
+        assert!(seen.contains(&(4, 3).into()) && seen.contains(&(5, 1).into()));
+        assert!(seen.contains(&(2, 1).into()) && !seen.contains(&(2, 0).into()));
+        assert!(!seen.contains(&(2, 2).into()));
+
+        let seen = map.visible(Point::new(0, 2), |&c| c == '#');
+        assert!(seen.contains(&(2, 1).into()) && !seen.contains(&(4, 0).into()));
+        // Shadows of (2, 1): (3..=5, 0) and (3..=4, 1), and of (4, 3): (5, 3).
+        assert_eq!(map.len() - 1 - 6, seen.len());
+        assert!(!seen.contains(&(3, 1).into()) && !seen.contains(&(5, 3).into()));
+
+        assert_eq!(
+            vec![Point::new(0, 0), Point::new(1, 1), Point::new(1, 2)],
+            bresenham(Point::new(0, 0), Point::new(1, 2))
+        );
+    }
+
+    #[test]
+    fn casting_across_gaps() {
+        let mut map = Map::new();
+        map.insert(Point::new(0, 0), '.');
+        map.insert(Point::new(5, 0), '#');
+        assert_eq!(
+            Some(((5, 0).into(), 5)),
+            map.cast(Point::new(0, 0), Direction::Right, |&c| c == '#')
+        );
+        assert_eq!(1, map.line(Point::new(0, 0), Direction::Right).count());
+    }
+}
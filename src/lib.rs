@@ -1,9 +1,32 @@
-use core::hash::Hash;
+use core::hash::{Hash, Hasher};
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use anyhow::anyhow;
 use anyhow::Result;
 
+// Lets code generated by the `Tile` derive name this crate from inside it.
+extern crate self as adv_code_2024;
+
+pub use grid::Grid;
+pub use tile_derive::Tile;
+
+pub mod automaton;
+pub mod bitgrid;
+pub mod chunked;
+pub mod compress;
+pub mod debugger;
+pub mod distance;
+pub mod export;
+pub mod graph;
+pub mod grid;
+pub mod playback;
+pub mod prefix;
+pub mod region;
+pub mod search;
+pub mod states;
+pub mod walker;
+
 pub fn start_day(day: &str) {
     println!("Advent of Code 2024 - Day {:0>2}", day);
 }
@@ -54,7 +77,33 @@ where
         }
     }
 }
-
+impl Point<i32> {
+    pub fn manhattan(&self, other: &Self) -> usize {
+        (self.x.abs_diff(other.x) + self.y.abs_diff(other.y)) as usize
+    }
+    pub fn neighbors4(&self) -> [Self; 4] {
+        Direction::ALL.map(|dir| *self + dir)
+    }
+    pub fn neighbors8(&self) -> [Self; 8] {
+        let [up, right, down, left] = self.neighbors4();
+        [
+            up,
+            up + Direction::Right,
+            right,
+            down + Direction::Right,
+            down,
+            down + Direction::Left,
+            left,
+            up + Direction::Left,
+        ]
+    }
+}
+impl std::ops::Add<Direction> for Point<i32> {
+    type Output = Self;
+    fn add(self, dir: Direction) -> Self {
+        self + Point::from(dir)
+    }
+}
 
 impl<T> Display for Point<T> where T: Display + Hash + Copy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -62,6 +111,83 @@ impl<T> Display for Point<T> where T: Display + Hash + Copy {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTile(pub char);
+
+impl Display for InvalidTile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid character: {}", self.0)
+    }
+}
+impl std::error::Error for InvalidTile {}
+
+// Inclusive on both corners, like Map's min and max.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub min: Point<i32>,
+    pub max: Point<i32>,
+}
+impl Rect {
+    pub fn new(min: Point<i32>, max: Point<i32>) -> Self {
+        Rect { min, max }
+    }
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1).max(0) as usize
+    }
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1).max(0) as usize
+    }
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+    pub fn contains(&self, point: &Point<i32>) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+    // Row-major position of a point, for dense storage over the rectangle.
+    pub fn index(&self, point: &Point<i32>) -> Option<usize> {
+        self.contains(point)
+            .then(|| (point.y - self.min.y) as usize * self.width() + (point.x - self.min.x) as usize)
+    }
+    pub fn points(&self) -> impl Iterator<Item = Point<i32>> {
+        let (min, max) = (self.min, self.max);
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point { x, y }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn turn_right(self) -> Self {
+        Self::ALL[(self.index() + 1) % 4]
+    }
+    pub fn turn_left(self) -> Self {
+        Self::ALL[(self.index() + 3) % 4]
+    }
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self.index() + 2) % 4]
+    }
+}
+impl From<Direction> for Point<i32> {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Up => Point { x: 0, y: -1 },
+            Direction::Right => Point { x: 1, y: 0 },
+            Direction::Down => Point { x: 0, y: 1 },
+            Direction::Left => Point { x: -1, y: 0 },
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Map<Tile> where  {
     map: HashMap<Point<i32>, Tile>,
@@ -69,6 +195,73 @@ pub struct Map<Tile> where  {
     pub max: Point<i32>,
 }
 
+// Equal when they hold the same tiles at the same points, whatever order
+// they were inserted in.
+impl<Tile> PartialEq for Map<Tile> where Tile: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+impl<Tile> Eq for Map<Tile> where Tile: Eq {}
+
+impl<Tile> Hash for Map<Tile> where Tile: Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.map.len());
+        state.write_u64(self.fingerprint());
+    }
+}
+
+// Fx-style multiply-rotate hasher, cheap enough to run on every cell.
+struct CellHasher(u64);
+
+impl Hasher for CellHasher {
+    fn finish(&self) -> u64 {
+        // splitmix64 finaliser so the per-cell sums below stay well mixed
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+    fn write_i32(&mut self, n: i32) {
+        self.write_u64(n as u32 as u64);
+    }
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+}
+
+impl<Tile> Map<Tile> where Tile: Hash {
+    /// 64-bit digest of the cells that doesn't depend on iteration order,
+    /// for spotting repeated board states.
+    pub fn fingerprint(&self) -> u64 {
+        self.map
+            .iter()
+            .map(|cell| {
+                let mut hasher = CellHasher(0);
+                cell.hash(&mut hasher);
+                hasher.finish()
+            })
+            .fold(0, u64::wrapping_add)
+    }
+}
+
+impl<Tile> Default for Map<Tile> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Tile> Map<Tile>
 {
     pub fn new() -> Self {
@@ -87,6 +280,23 @@ impl<Tile> Map<Tile>
         self.min = self.min.min(&point);
         self.map.insert(point, tile);
     }
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.min, self.max)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&Point<i32>, &Tile)> {
+        self.map.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    pub fn neighbors(&self, point: Point<i32>) -> impl Iterator<Item = (Point<i32>, &Tile)> {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |dir| self.get(&(point + dir)).map(|tile| (point + dir, tile)))
+    }
     pub fn parse(reader: impl std::io::BufRead) -> Result<Self>
     where
     Tile: TryFrom<char>{
@@ -113,6 +323,64 @@ impl<Tile> Map<Tile>
         }
         Ok((map, start_point))
     }
+    /// Splits each line into cells with `cells` and parses every one with
+    /// `FromStr`, e.g. a comma separated matrix straight into a `Map<i64>`.
+    pub fn parse_cells(reader: impl std::io::BufRead, cells: Cells) -> Result<Self>
+    where Tile: FromStr {
+        let mut map: Map<Tile> = Self::new();
+        for (y, line) in reader.lines().enumerate() {
+            let line = line?;
+            let tokens: Vec<String> = match cells {
+                Cells::Fixed(width) => line
+                    .chars()
+                    .collect::<Vec<_>>()
+                    .chunks(width.max(1))
+                    .map(|chunk| chunk.iter().collect())
+                    .collect(),
+                Cells::Delimited(delimiter) => line.split(delimiter).map(String::from).collect(),
+                Cells::Whitespace => line.split_whitespace().map(String::from).collect(),
+            };
+            for (x, token) in tokens.iter().enumerate() {
+                let tile = match token.trim().parse() {
+                    Ok(tile) => tile,
+                    Err(_) => return Err(anyhow!("Invalid cell: {}", token)),
+                };
+                map.insert((x as i32, y as i32).into(), tile);
+            }
+        }
+        Ok(map)
+    }
+}
+
+// How `Map::parse_cells` splits a line into cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cells {
+    Fixed(usize),
+    Delimited(char),
+    Whitespace,
+}
+
+impl Map<u8> {
+    // Height maps and the like, one decimal digit per cell.
+    pub fn parse_digits(reader: impl std::io::BufRead) -> Result<Self> {
+        let mut map = Self::new();
+        for (y, line) in reader.lines().enumerate() {
+            for (x, c) in line?.chars().enumerate() {
+                let Some(digit) = c.to_digit(10) else {
+                    return Err(anyhow!("Invalid digit: {}", c));
+                };
+                map.insert((x as i32, y as i32).into(), digit as u8);
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl Map<char> {
+    // Keeps every char as it is.
+    pub fn parse_chars(reader: impl std::io::BufRead) -> Result<Self> {
+        Self::parse(reader)
+    }
 }
 
 #[cfg(test)]
@@ -123,4 +391,61 @@ mod tests {
     fn it_works() {
         start_day("00");
     }
+
+    #[test]
+    fn parse_variants() {
+        let digits = Map::parse_digits("012\n987".as_bytes()).unwrap();
+        assert_eq!(Some(&9), digits.get(&(0, 1).into()));
+        assert!(Map::parse_digits("01x".as_bytes()).is_err());
+
+        let chars = Map::parse_chars("a#".as_bytes()).unwrap();
+        assert_eq!(Some(&'#'), chars.get(&(1, 0).into()));
+
+        let csv: Map<i64> = Map::parse_cells("1,-20,300\n4, 5,6".as_bytes(), Cells::Delimited(',')).unwrap();
+        assert_eq!((Some(&-20), Some(&5)), (csv.get(&(1, 0).into()), csv.get(&(1, 1).into())));
+        let fixed: Map<i64> = Map::parse_cells(" 123-4".as_bytes(), Cells::Fixed(2)).unwrap();
+        assert_eq!(vec![1, 23, -4], (0..3).map(|x| *fixed.get(&(x, 0).into()).unwrap()).collect::<Vec<_>>());
+        let spaced: Map<i64> = Map::parse_cells("3   4\n4   3".as_bytes(), Cells::Whitespace).unwrap();
+        assert_eq!(Point::new(1, 1), spaced.max);
+        assert!(Map::<i64>::parse_cells("1,a".as_bytes(), Cells::Delimited(',')).is_err());
+    }
+
+    #[test]
+    fn map_identity() {
+        let mut a: Map<char> = Map::new();
+        let mut b: Map<char> = Map::new();
+        let cells: Vec<(Point<i32>, char)> = (0..50)
+            .map(|i| ((i % 7, i / 7).into(), (b'a' + i as u8 % 5) as char))
+            .collect();
+        cells.iter().for_each(|&(p, c)| a.insert(p, c));
+        cells.iter().rev().for_each(|&(p, c)| b.insert(p, c));
+        assert!(a == b);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        let mut seen = HashMap::new();
+        seen.insert(a.clone(), 0);
+        assert_eq!(Some(&0), seen.get(&b));
+        b.insert((0, 0).into(), 'z');
+        assert!(a != b);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+        assert_eq!(None, seen.get(&b));
+    }
+
+    #[derive(Debug, PartialEq, Tile)]
+    enum Cell {
+        #[tile('#')]
+        Wall,
+        #[tile('.', 'S')]
+        Open,
+    }
+
+    #[test]
+    fn derive_tile() {
+        assert_eq!(Ok(Cell::Open), Cell::try_from('S'));
+        assert_eq!(Err(InvalidTile('x')), Cell::try_from('x'));
+        assert_eq!('.', char::from(Cell::Open));
+        assert_eq!("#.", Cell::ALL.iter().map(|c| c.to_string()).collect::<String>());
+        let map: Map<Cell> = Map::parse("#S.".as_bytes()).unwrap();
+        assert_eq!(Some(&Cell::Open), map.get(&(1, 0).into()));
+    }
 }
@@ -0,0 +1,366 @@
+use crate::{Direction, Map, Point};
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entity {
+    pub pos: Point<i32>,
+    pub glyph: char,
+}
+
+struct Frame<Tile> {
+    changes: Vec<(Point<i32>, Tile)>,
+    entities: Vec<Entity>,
+}
+
+/// Records a simulation as a starting map plus, for every frame, the cells
+/// that changed and where the entities are.
+pub struct Recorder<Tile> {
+    base: Map<Tile>,
+    last: Map<Tile>,
+    frames: Vec<Frame<Tile>>,
+}
+
+impl<Tile> Recorder<Tile>
+where
+    Tile: Clone + PartialEq,
+{
+    pub fn new(map: &Map<Tile>) -> Self {
+        Recorder {
+            base: map.clone(),
+            last: map.clone(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Records the whole map, storing only the cells that differ from the
+    /// previous frame.
+    pub fn snapshot(&mut self, map: &Map<Tile>, entities: Vec<Entity>) {
+        let changes = map
+            .iter()
+            .filter(|(point, tile)| self.last.get(point) != Some(tile))
+            .map(|(point, tile)| (*point, tile.clone()))
+            .collect();
+        self.delta(changes, entities);
+    }
+
+    pub fn delta(&mut self, changes: Vec<(Point<i32>, Tile)>, entities: Vec<Entity>) {
+        for (point, tile) in &changes {
+            self.last.insert(*point, tile.clone());
+        }
+        self.frames.push(Frame { changes, entities });
+    }
+
+    /// The map as it was at `frame`, or `None` past the last frame.
+    pub fn render(&self, frame: usize, glyph: impl Fn(&Tile) -> char) -> Option<String> {
+        let entities = &self.frames.get(frame)?.entities;
+        let mut map = self.base.clone();
+        for frame in &self.frames[..=frame] {
+            apply(&mut map, frame);
+        }
+        Some(render(&map, entities, &glyph))
+    }
+
+    /// Every frame one after the other, each under a `Frame n` header.
+    pub fn write_text(&self, path: impl AsRef<Path>, glyph: impl Fn(&Tile) -> char) -> Result<()> {
+        let mut map = self.base.clone();
+        let mut text = String::new();
+        for (i, frame) in self.frames.iter().enumerate() {
+            apply(&mut map, frame);
+            text.push_str(&format!(
+                "Frame {}\n{}\n",
+                i,
+                render(&map, &frame.entities, &glyph)
+            ));
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn player<G>(&self, glyph: G) -> Player<'_, Tile, G>
+    where
+        G: Fn(&Tile) -> char,
+    {
+        Player {
+            recorder: self,
+            glyph,
+            map: self.base.clone(),
+            index: None,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+fn apply<Tile: Clone>(map: &mut Map<Tile>, frame: &Frame<Tile>) {
+    for (point, tile) in &frame.changes {
+        map.insert(*point, tile.clone());
+    }
+}
+
+fn render<Tile>(map: &Map<Tile>, entities: &[Entity], glyph: &impl Fn(&Tile) -> char) -> String {
+    let mut result = String::new();
+    for y in map.min.y..=map.max.y {
+        for x in map.min.x..=map.max.x {
+            let point = Point { x, y };
+            let c = match entities.iter().rev().find(|e| e.pos == point) {
+                Some(entity) => entity.glyph,
+                None => map.get(&point).map_or(' ', glyph),
+            };
+            result.push(c);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// A command for the terminal tools, shared by `Player` and the walker
+/// debugger so the same keys do the same thing in both. Each tool answers
+/// the commands it has no use for with its help line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Moves forward this many frames or steps.
+    Next(usize),
+    /// Moves back this many frames or steps.
+    Previous(usize),
+    Jump(usize),
+    /// Plays `n` frames (or to the end) at the current speed, then pauses.
+    /// Input isn't read while frames are playing, so a bounded run is the
+    /// way to stop partway.
+    Run(Option<usize>),
+    Speed(Duration),
+    /// Stops a run on reaching a position, or a position and facing.
+    Break(Point<i32>, Option<Direction>),
+    ClearBreakpoints,
+    /// How many cells to show around the walker.
+    Window(i32),
+    Quit,
+}
+
+impl Command {
+    /// `n [count]` (or an empty line), `p [count]`, `j <frame>`, `r [frames]`,
+    /// `s <ms>`, `b <x> <y> [U|R|D|L]`, `d`, `w <radius>`, `q`.
+    pub fn parse(line: &str) -> Option<Command> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| parts.get(i)?.parse::<i32>().ok();
+        let count = |i: usize| usize::try_from(number(i)?).ok();
+        let command = match (parts.first().copied(), parts.len()) {
+            (None, _) | (Some("n"), 1) => Command::Next(1),
+            (Some("n"), 2) => Command::Next(count(1)?),
+            (Some("p"), 1) => Command::Previous(1),
+            (Some("p"), 2) => Command::Previous(count(1)?),
+            (Some("j"), 2) => Command::Jump(count(1)?),
+            (Some("r"), 1) => Command::Run(None),
+            (Some("r"), 2) => Command::Run(Some(count(1)?)),
+            (Some("s"), 2) => Command::Speed(Duration::from_millis(count(1)? as u64)),
+            (Some("b"), 3 | 4) => {
+                let dir = match parts.get(3).copied() {
+                    None => None,
+                    Some("U") => Some(Direction::Up),
+                    Some("R") => Some(Direction::Right),
+                    Some("D") => Some(Direction::Down),
+                    Some("L") => Some(Direction::Left),
+                    _ => return None,
+                };
+                Command::Break(Point::new(number(1)?, number(2)?), dir)
+            }
+            (Some("d"), 1) => Command::ClearBreakpoints,
+            (Some("w"), 2) => Command::Window(number(1)?.max(0)),
+            (Some("q"), 1) => Command::Quit,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Plays a recording back in the terminal, redrawing in place with ANSI
+/// escapes. Between commands playback is paused on the current frame.
+/// Playing blocks until its frames are drawn: there is no key to pause a
+/// run midway, so use `r <frames>` to play a stretch and pause after it.
+pub struct Player<'a, Tile, G> {
+    recorder: &'a Recorder<Tile>,
+    glyph: G,
+    map: Map<Tile>,
+    index: Option<usize>,
+    delay: Duration,
+}
+
+impl<'a, Tile, G> Player<'a, Tile, G>
+where
+    Tile: Clone + PartialEq,
+    G: Fn(&Tile) -> char,
+{
+    pub fn frame(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Does nothing on an empty recording, which has no frame to show.
+    pub fn seek(&mut self, frame: usize) {
+        if self.recorder.is_empty() {
+            return;
+        }
+        let frame = frame.min(self.recorder.len().saturating_sub(1));
+        let mut from = self.index.map_or(0, |i| i + 1);
+        if self.index.is_some_and(|i| i > frame) {
+            self.map = self.recorder.base.clone();
+            from = 0;
+        }
+        for frame in &self.recorder.frames[from..=frame] {
+            apply(&mut self.map, frame);
+        }
+        self.index = Some(frame);
+    }
+
+    pub fn draw(&self, out: &mut impl Write) -> Result<()> {
+        let Some(index) = self.index else {
+            return Ok(());
+        };
+        let entities = &self.recorder.frames[index].entities;
+        write!(
+            out,
+            "\x1b[H\x1b[2J{}",
+            render(&self.map, entities, &self.glyph)
+        )?;
+        writeln!(
+            out,
+            "frame {}/{}  delay {}ms",
+            index,
+            self.recorder.len() - 1,
+            self.delay.as_millis()
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Plays from the current frame to the end without waiting for input,
+    /// and can't be paused before the end.
+    pub fn play(&mut self, out: &mut impl Write) -> Result<()> {
+        self.run(out, None)
+    }
+
+    fn run(&mut self, out: &mut impl Write, frames: Option<usize>) -> Result<()> {
+        if self.recorder.is_empty() {
+            return Ok(());
+        }
+        let last = self.recorder.len().saturating_sub(1);
+        let start = self.index.map_or(0, |i| i + 1);
+        let end = frames.map_or(last, |n| (start + n).saturating_sub(1).min(last));
+        for frame in start..=end {
+            self.seek(frame);
+            self.draw(out)?;
+            thread::sleep(self.delay);
+        }
+        Ok(())
+    }
+
+    /// Reads one command per line from `input` until `q` or end of input.
+    pub fn interactive(&mut self, input: impl BufRead, out: &mut impl Write) -> Result<()> {
+        if self.recorder.is_empty() {
+            return Ok(());
+        }
+        self.seek(self.index.unwrap_or(0));
+        self.draw(out)?;
+        for line in input.lines() {
+            match Command::parse(&line?) {
+                Some(Command::Next(n)) => self.seek(self.index.map_or(0, |i| i + n)),
+                Some(Command::Previous(n)) => {
+                    self.seek(self.index.map_or(0, |i| i.saturating_sub(n)))
+                }
+                Some(Command::Jump(frame)) => self.seek(frame),
+                Some(Command::Run(frames)) => self.run(out, frames)?,
+                Some(Command::Speed(delay)) => self.delay = delay,
+                Some(Command::Quit) => break,
+                _ => {
+                    writeln!(
+                        out,
+                        "commands: n [frames], p [frames], j <frame>, r [frames], s <ms>, q"
+                    )?;
+                    continue;
+                }
+            }
+            self.draw(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> Recorder<char> {
+        let mut map: Map<char> = Map::parse("...\n.#.".as_bytes()).unwrap();
+        let mut recorder = Recorder::new(&map);
+        for x in 0..3 {
+            map.insert((x, 0).into(), 'X');
+            recorder.snapshot(
+                &map,
+                vec![Entity {
+                    pos: (x, 0).into(),
+                    glyph: '>',
+                }],
+            );
+        }
+        recorder
+    }
+
+    #[test]
+    fn records_deltas() {
+        let recorder = recording();
+        assert_eq!(3, recorder.len());
+        assert!(recorder.frames.iter().all(|f| f.changes.len() == 1));
+        assert_eq!(Some("X>.\n.#.\n".to_string()), recorder.render(1, |&c| c));
+        assert_eq!(None, recorder.render(3, |&c| c));
+
+        let path = std::env::temp_dir().join("adv-code-2024-frames.txt");
+        recorder.write_text(&path, |&c| c).unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.starts_with("Frame 0\n>..\n.#.\n\nFrame 1\n"));
+    }
+
+    #[test]
+    fn interactive_controls() {
+        let recorder = recording();
+        let mut player = recorder.player(|&c| c);
+        let mut out = Vec::new();
+        player
+            .interactive("s 0\nn\nj 0\nr\np\n?\nq\nn\n".as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(Some(1), player.frame());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\x1b[H\x1b[2JXX>\n"));
+        assert!(out.contains("commands:"));
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Some(Command::Next(1)), Command::parse(""));
+        assert_eq!(Some(Command::Next(10)), Command::parse("n 10"));
+        assert_eq!(Some(Command::Run(Some(5))), Command::parse("r 5"));
+        assert_eq!(
+            Some(Command::Break((2, -3).into(), Some(Direction::Left))),
+            Command::parse("b 2 -3 L")
+        );
+        assert_eq!(None, Command::parse("j"));
+        assert_eq!(None, Command::parse("b 2"));
+        assert_eq!(None, Command::parse("p -1"));
+    }
+
+    #[test]
+    fn empty_recording() {
+        let recorder = Recorder::new(&Map::<char>::new());
+        assert_eq!(None, recorder.render(0, |&c| c));
+        let mut player = recorder.player(|&c| c);
+        player.seek(3);
+        assert_eq!(None, player.frame());
+    }
+}
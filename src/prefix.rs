@@ -0,0 +1,119 @@
+use crate::{Map, Point, Rect};
+
+/// Summed-area table over a map's rectangle: `sums[y][x]` (one row and
+/// column wider than the map) holds the total of every cell above and to
+/// the left of `(x, y)`, so any rectangle sum is four lookups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixSum2D {
+    rect: Rect,
+    sums: Vec<i64>,
+}
+
+impl PrefixSum2D {
+    /// Missing cells count as 0.
+    pub fn from_map<Tile>(map: &Map<Tile>, mut value: impl FnMut(&Tile) -> i64) -> Self {
+        let rect = map.rect();
+        let stride = rect.width() + 1;
+        let mut sums = vec![0; stride * (rect.height() + 1)];
+        for (y, row) in (rect.min.y..=rect.max.y).enumerate() {
+            let mut across = 0;
+            for (x, column) in (rect.min.x..=rect.max.x).enumerate() {
+                across += map.get(&Point::new(column, row)).map_or(0, &mut value);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + across;
+            }
+        }
+        PrefixSum2D { rect, sums }
+    }
+
+    pub fn new<Tile>(map: &Map<Tile>) -> Self
+    where
+        Tile: Copy + Into<i64>,
+    {
+        Self::from_map(map, |&tile| tile.into())
+    }
+
+    /// Counts the cells that match rather than summing them.
+    pub fn count<Tile>(map: &Map<Tile>, mut matches: impl FnMut(&Tile) -> bool) -> Self {
+        Self::from_map(map, |tile| matches(tile) as i64)
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    fn at(&self, x: i32, y: i32) -> i64 {
+        let stride = self.rect.width() + 1;
+        let x = (x - self.rect.min.x).clamp(0, stride as i32 - 1) as usize;
+        let y = (y - self.rect.min.y).clamp(0, self.rect.height() as i32) as usize;
+        self.sums[y * stride + x]
+    }
+
+    /// Total over `rect`, ignoring whatever part of it is off the map.
+    pub fn sum(&self, rect: &Rect) -> i64 {
+        if rect.area() == 0 {
+            return 0;
+        }
+        let (min, max) = (rect.min, rect.max);
+        self.at(max.x + 1, max.y + 1) - self.at(min.x, max.y + 1) - self.at(max.x + 1, min.y)
+            + self.at(min.x, min.y)
+    }
+
+    /// Every `width` x `height` window that fits on the map with its total,
+    /// in row-major order of the top left corner.
+    pub fn windows(&self, width: usize, height: usize) -> impl Iterator<Item = (Rect, i64)> + '_ {
+        let (w, h) = (width as i32, height as i32);
+        let fits = width > 0 && height > 0;
+        let corners = Rect::new(self.rect.min, self.rect.max + Point::new(1 - w, 1 - h));
+        corners.points().filter(move |_| fits).map(move |min| {
+            let window = Rect::new(min, min + Point::new(w - 1, h - 1));
+            (window, self.sum(&window))
+        })
+    }
+
+    /// The window of the given size with the largest total, the first one
+    /// in row-major order on ties.
+    pub fn max_window(&self, width: usize, height: usize) -> Option<(Rect, i64)> {
+        self.windows(width, height)
+            .fold(None, |best, (rect, sum)| match best {
+                Some((_, top)) if top >= sum => best,
+                _ => Some((rect, sum)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_sums() {
+        let map = Map::parse_digits("123\n456\n789".as_bytes()).unwrap();
+        let sums = PrefixSum2D::new(&map);
+        assert_eq!(45, sums.sum(&map.rect()));
+        assert_eq!(
+            5 + 6 + 8 + 9,
+            sums.sum(&Rect::new((1, 1).into(), (2, 2).into()))
+        );
+        assert_eq!(4, sums.sum(&Rect::new((-3, 1).into(), (0, 1).into())));
+        assert_eq!(0, sums.sum(&Rect::new((2, 2).into(), (1, 2).into())));
+
+        let windows: Vec<i64> = sums.windows(2, 2).map(|(_, s)| s).collect();
+        assert_eq!(vec![12, 16, 24, 28], windows);
+        assert_eq!(
+            Some((Rect::new((1, 1).into(), (2, 2).into()), 28)),
+            sums.max_window(2, 2)
+        );
+        assert_eq!(None, sums.max_window(4, 1));
+        assert_eq!(3, sums.windows(1, 3).count());
+    }
+
+    #[test]
+    fn counting_matches() {
+        let map: Map<char> = Map::parse("#.#.\n.##.\n#..#".as_bytes()).unwrap();
+        let walls = PrefixSum2D::count(&map, |&c| c == '#');
+        assert_eq!(6, walls.sum(&map.rect()));
+        assert_eq!(3, walls.sum(&Rect::new((1, 0).into(), (2, 1).into())));
+        let brute = |r: &Rect| r.points().filter(|p| map.get(p) == Some(&'#')).count() as i64;
+        assert!(walls.windows(3, 2).all(|(r, n)| n == brute(&r)));
+    }
+}
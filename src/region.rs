@@ -0,0 +1,170 @@
+use crate::{Direction, Map, Point, Rect};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbors(self, point: &Point<i32>) -> Vec<Point<i32>> {
+        match self {
+            Connectivity::Four => point.neighbors4().to_vec(),
+            Connectivity::Eight => point.neighbors8().to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub cells: HashSet<Point<i32>>,
+}
+
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Edges between a cell of the region and one outside it.
+    pub fn perimeter(&self) -> usize {
+        self.cells
+            .iter()
+            .flat_map(|cell| cell.neighbors4())
+            .filter(|next| !self.cells.contains(next))
+            .count()
+    }
+
+    // A closed outline has as many corners as straight sides, holes included.
+    pub fn corners(&self) -> usize {
+        let turns = [
+            (Direction::Up, Direction::Right),
+            (Direction::Right, Direction::Down),
+            (Direction::Down, Direction::Left),
+            (Direction::Left, Direction::Up),
+        ];
+        self.cells
+            .iter()
+            .map(|&cell| {
+                turns
+                    .iter()
+                    .filter(|&&(a, b)| {
+                        let side_a = self.cells.contains(&(cell + a));
+                        let side_b = self.cells.contains(&(cell + b));
+                        let diagonal = self.cells.contains(&(cell + a + b));
+                        (!side_a && !side_b) || (side_a && side_b && !diagonal)
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    pub fn sides(&self) -> usize {
+        self.corners()
+    }
+
+    pub fn bounds(&self) -> Rect {
+        let mut cells = self.cells.iter();
+        let first = *cells.next().expect("regions are never empty");
+        let (min, max) = cells.fold((first, first), |(min, max), cell| {
+            (min.min(cell), max.max(cell))
+        });
+        Rect::new(min, max)
+    }
+}
+
+impl<Tile> Map<Tile> {
+    /// Every cell reachable from `start` through orthogonal steps where
+    /// `connected(from, to)` holds.
+    pub fn flood_fill(
+        &self,
+        start: Point<i32>,
+        connected: impl FnMut(&Tile, &Tile) -> bool,
+    ) -> HashSet<Point<i32>> {
+        self.flood(start, Connectivity::Four, connected)
+    }
+
+    fn flood(
+        &self,
+        start: Point<i32>,
+        connectivity: Connectivity,
+        mut connected: impl FnMut(&Tile, &Tile) -> bool,
+    ) -> HashSet<Point<i32>> {
+        let mut cells = HashSet::new();
+        if self.get(&start).is_none() {
+            return cells;
+        }
+        cells.insert(start);
+        let mut stack = vec![start];
+        while let Some(point) = stack.pop() {
+            let tile = &self.map[&point];
+            for next in connectivity.neighbors(&point) {
+                if cells.contains(&next) {
+                    continue;
+                }
+                if let Some(next_tile) = self.get(&next) {
+                    if connected(tile, next_tile) {
+                        cells.insert(next);
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// 4-connected regions of cells where `same(a, b)` holds between neighbours.
+    pub fn regions(&self, same: impl FnMut(&Tile, &Tile) -> bool) -> Vec<Region> {
+        self.regions_with(Connectivity::Four, same)
+    }
+
+    /// Regions are returned in row-major order of their first cell, so the
+    /// index of a region in the result is a stable label for it.
+    pub fn regions_with(
+        &self,
+        connectivity: Connectivity,
+        mut same: impl FnMut(&Tile, &Tile) -> bool,
+    ) -> Vec<Region> {
+        let mut labelled = HashSet::new();
+        let mut regions = Vec::new();
+        for point in self.rect().points() {
+            if labelled.contains(&point) || self.get(&point).is_none() {
+                continue;
+            }
+            let cells = self.flood(point, connectivity, &mut same);
+            labelled.extend(cells.iter().copied());
+            regions.push(Region { cells });
+        }
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GARDEN: &str = "\
+AAAA
+BBCD
+BBCC
+EEEC";
+
+    #[test]
+    fn garden_regions() {
+        let map: Map<char> = Map::parse(GARDEN.as_bytes()).unwrap();
+        let regions = map.regions(|a, b| a == b);
+        let stats: Vec<_> = regions
+            .iter()
+            .map(|r| (r.area(), r.perimeter(), r.sides()))
+            .collect();
+        assert_eq!(
+            vec![(4, 10, 4), (4, 8, 4), (4, 10, 8), (1, 4, 4), (3, 8, 4)],
+            stats
+        );
+        assert_eq!(Rect::new((2, 1).into(), (3, 3).into()), regions[2].bounds());
+
+        let diagonal = map.regions_with(Connectivity::Eight, |a, b| a == b);
+        assert_eq!(5, diagonal.len());
+        assert_eq!(4, map.flood_fill((0, 0).into(), |a, b| a == b).len());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{Map, Point};
+use priority_queue::PriorityQueue;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Result of a shortest path search: the distance to the goal, one shortest
+/// path (start and goal included) and the number of distinct shortest paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<S> {
+    pub distance: usize,
+    pub path: Vec<S>,
+    pub count: usize,
+}
+
+struct Visit<S> {
+    distance: usize,
+    count: usize,
+    parent: Option<S>,
+}
+
+fn reconstruct<S>(visits: &HashMap<S, Visit<S>>, goal: S) -> Vec<S>
+where
+    S: Clone + Eq + Hash,
+{
+    let mut path = vec![goal];
+    while let Some(parent) = &visits[path.last().unwrap()].parent {
+        path.push(parent.clone());
+    }
+    path.reverse();
+    path
+}
+
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut visits = HashMap::new();
+    visits.insert(
+        start.clone(),
+        Visit {
+            distance: 0,
+            count: 1,
+            parent: None,
+        },
+    );
+    let mut queue = VecDeque::from([start]);
+    let mut found: Option<(S, usize, usize)> = None;
+
+    while let Some(state) = queue.pop_front() {
+        let (distance, count) = {
+            let visit = &visits[&state];
+            (visit.distance, visit.count)
+        };
+        if let Some((_, best, _)) = &found {
+            if distance > *best {
+                break;
+            }
+        }
+        if goal(&state) {
+            match &mut found {
+                Some((_, _, total)) => *total = total.saturating_add(count),
+                None => found = Some((state, distance, count)),
+            }
+            continue;
+        }
+        for next in successors(&state) {
+            match visits.get_mut(&next) {
+                Some(visit) if visit.distance == distance + 1 => {
+                    visit.count = visit.count.saturating_add(count)
+                }
+                Some(_) => {}
+                None => {
+                    let visit = Visit {
+                        distance: distance + 1,
+                        count,
+                        parent: Some(state.clone()),
+                    };
+                    visits.insert(next.clone(), visit);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    found.map(|(goal, distance, count)| SearchResult {
+        distance,
+        path: reconstruct(&visits, goal),
+        count,
+    })
+}
+
+/// Edge costs must be positive for the path count to be exact.
+pub fn dijkstra<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    astar(start, successors, |_| 0, goal)
+}
+
+/// The heuristic must be consistent (never overestimating a single edge) for
+/// the result to be optimal, and edge costs must be positive for the path
+/// count to be exact.
+pub fn astar<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> usize,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    let mut visits = HashMap::new();
+    visits.insert(
+        start.clone(),
+        Visit {
+            distance: 0,
+            count: 1,
+            parent: None,
+        },
+    );
+    // Ties on the estimate are broken by distance so every predecessor on a
+    // shortest path is expanded before the states it leads to.
+    let mut queue = PriorityQueue::new();
+    queue.push(start.clone(), Reverse((heuristic(&start), 0)));
+    let mut found: Option<(S, usize, usize)> = None;
+
+    while let Some((state, Reverse((estimate, _)))) = queue.pop() {
+        if let Some((_, best, _)) = &found {
+            if estimate > *best {
+                break;
+            }
+        }
+        let (distance, count) = {
+            let visit = &visits[&state];
+            (visit.distance, visit.count)
+        };
+        if goal(&state) {
+            match &mut found {
+                Some((_, _, total)) => *total = total.saturating_add(count),
+                None => found = Some((state, distance, count)),
+            }
+            continue;
+        }
+        for (next, cost) in successors(&state) {
+            let next_distance = distance + cost;
+            match visits.get_mut(&next) {
+                Some(visit) if visit.distance < next_distance => continue,
+                Some(visit) if visit.distance == next_distance => {
+                    visit.count = visit.count.saturating_add(count);
+                    continue;
+                }
+                _ => {
+                    let visit = Visit {
+                        distance: next_distance,
+                        count,
+                        parent: Some(state.clone()),
+                    };
+                    visits.insert(next.clone(), visit);
+                }
+            }
+            let estimate = next_distance + heuristic(&next);
+            queue.push_increase(next, Reverse((estimate, next_distance)));
+        }
+    }
+
+    found.map(|(goal, distance, count)| SearchResult {
+        distance,
+        path: reconstruct(&visits, goal),
+        count,
+    })
+}
+
+impl<Tile> Map<Tile> {
+    pub fn bfs(
+        &self,
+        start: Point<i32>,
+        end: Point<i32>,
+        passable: impl Fn(&Tile) -> bool,
+    ) -> Option<SearchResult<Point<i32>>> {
+        let passable = &passable;
+        bfs(
+            start,
+            move |&point| {
+                self.neighbors(point)
+                    .filter(|(_, tile)| passable(tile))
+                    .map(|(next, _)| next)
+            },
+            |point| *point == end,
+        )
+    }
+
+    /// `cost` gives the price of stepping onto a tile, `None` if it can't be entered.
+    pub fn dijkstra(
+        &self,
+        start: Point<i32>,
+        end: Point<i32>,
+        cost: impl Fn(&Tile) -> Option<usize>,
+    ) -> Option<SearchResult<Point<i32>>> {
+        let cost = &cost;
+        dijkstra(
+            start,
+            move |&point| {
+                self.neighbors(point)
+                    .filter_map(|(next, tile)| cost(tile).map(|c| (next, c)))
+            },
+            |point| *point == end,
+        )
+    }
+
+    /// Like `dijkstra` with a Manhattan distance heuristic, so every tile
+    /// that can be entered must cost at least 1.
+    pub fn astar(
+        &self,
+        start: Point<i32>,
+        end: Point<i32>,
+        cost: impl Fn(&Tile) -> Option<usize>,
+    ) -> Option<SearchResult<Point<i32>>> {
+        let cost = &cost;
+        astar(
+            start,
+            move |&point| {
+                self.neighbors(point)
+                    .filter_map(|(next, tile)| cost(tile).map(|c| (next, c)))
+            },
+            |point| point.manhattan(&end),
+            |point| *point == end,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    const MAZE: &str = "\
+S..#
+.#..
+...E";
+
+    fn maze() -> (Map<char>, Point<i32>, Point<i32>) {
+        let map: Map<char> = Map::parse(MAZE.as_bytes()).unwrap();
+        (map, Point::new(0, 0), Point::new(3, 2))
+    }
+
+    #[test]
+    fn bfs_counts_shortest_paths() {
+        let (map, start, end) = maze();
+        let found = map.bfs(start, end, |&c| c != '#').unwrap();
+        assert_eq!(5, found.distance);
+        assert_eq!(3, found.count);
+        assert_eq!(6, found.path.len());
+        assert_eq!((start, end), (found.path[0], found.path[5]));
+
+        let weighted = map
+            .dijkstra(start, end, |&c| (c != '#').then_some(1))
+            .unwrap();
+        assert_eq!(found.distance, weighted.distance);
+        assert_eq!(found.count, weighted.count);
+        let estimated = map.astar(start, end, |&c| (c != '#').then_some(1)).unwrap();
+        assert_eq!(found.distance, estimated.distance);
+        assert_eq!(found.count, estimated.count);
+    }
+
+    #[test]
+    fn dijkstra_over_position_and_direction() {
+        let (map, start, end) = maze();
+        // Moving forward costs 1, turning in place costs 1000.
+        let found = dijkstra(
+            (start, Direction::Right),
+            |&(pos, dir)| {
+                let mut next = vec![
+                    ((pos, dir.turn_left()), 1000),
+                    ((pos, dir.turn_right()), 1000),
+                ];
+                if matches!(map.get(&(pos + dir)), Some(c) if *c != '#') {
+                    next.push(((pos + dir, dir), 1));
+                }
+                next
+            },
+            |&(pos, _)| pos == end,
+        )
+        .unwrap();
+        assert_eq!(2005, found.distance);
+        assert_eq!(2, found.count);
+        assert!(map.bfs(start, Point::new(3, 0), |&c| c != '#').is_none());
+        assert!(map.bfs(start, Point::new(9, 9), |&c| c != '#').is_none());
+    }
+}
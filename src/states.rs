@@ -0,0 +1,147 @@
+use crate::{Direction, Map, Point, Rect};
+use std::collections::HashMap;
+
+/// Visited (position, direction) pairs, kept as four bits per cell. Cells
+/// inside `rect` live in a dense array; anything outside it is hashed.
+#[derive(Debug, Clone)]
+pub struct StateSet {
+    rect: Rect,
+    masks: Vec<u8>,
+    // Dense cells in the order they were first set, so clearing and
+    // iterating only visit what was used.
+    touched: Vec<usize>,
+    outside: HashMap<Point<i32>, u8>,
+    len: usize,
+}
+
+impl StateSet {
+    pub fn new(rect: Rect) -> Self {
+        StateSet {
+            rect,
+            masks: vec![0; rect.area()],
+            touched: Vec::new(),
+            outside: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn for_map<Tile>(map: &Map<Tile>) -> Self {
+        Self::new(map.rect())
+    }
+
+    /// Everything hashed, for walkers with no fixed bounds.
+    pub fn unbounded() -> Self {
+        Self::new(Rect::new((0, 0).into(), (-1, -1).into()))
+    }
+
+    /// Whether the pair wasn't already in the set.
+    pub fn insert(&mut self, pos: Point<i32>, dir: Direction) -> bool {
+        let bit = 1 << dir.index();
+        let mask = match self.rect.index(&pos) {
+            Some(i) => {
+                if self.masks[i] == 0 {
+                    self.touched.push(i);
+                }
+                &mut self.masks[i]
+            }
+            None => self.outside.entry(pos).or_default(),
+        };
+        let new = *mask & bit == 0;
+        *mask |= bit;
+        self.len += new as usize;
+        new
+    }
+
+    pub fn contains(&self, pos: &Point<i32>, dir: Direction) -> bool {
+        self.mask(pos) & (1 << dir.index()) != 0
+    }
+
+    pub fn contains_cell(&self, pos: &Point<i32>) -> bool {
+        self.mask(pos) != 0
+    }
+
+    fn mask(&self, pos: &Point<i32>) -> u8 {
+        match self.rect.index(pos) {
+            Some(i) => self.masks[i],
+            None => self.outside.get(pos).copied().unwrap_or(0),
+        }
+    }
+
+    /// Directions recorded at `pos`, in `Direction::ALL` order.
+    pub fn directions(&self, pos: &Point<i32>) -> impl Iterator<Item = Direction> {
+        let mask = self.mask(pos);
+        Direction::ALL
+            .into_iter()
+            .filter(move |dir| mask & (1 << dir.index()) != 0)
+    }
+
+    /// Number of pairs.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of distinct positions.
+    pub fn cell_count(&self) -> usize {
+        self.touched.len() + self.outside.len()
+    }
+
+    /// Empties the set in time proportional to the cells that were used.
+    pub fn clear(&mut self) {
+        for i in self.touched.drain(..) {
+            self.masks[i] = 0;
+        }
+        self.outside.clear();
+        self.len = 0;
+    }
+
+    /// Distinct positions, dense ones in the order they were first visited.
+    pub fn cells(&self) -> impl Iterator<Item = Point<i32>> + '_ {
+        let (min, width) = (self.rect.min, self.rect.width());
+        self.touched
+            .iter()
+            .map(move |&i| min + Point::new((i % width) as i32, (i / width) as i32))
+            .chain(self.outside.keys().copied())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i32>, Direction)> + '_ {
+        self.cells()
+            .flat_map(move |pos| self.directions(&pos).map(move |dir| (pos, dir)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense_and_outside() {
+        let mut set = StateSet::new(Rect::new((0, 0).into(), (2, 2).into()));
+        assert!(set.insert((1, 1).into(), Direction::Up));
+        assert!(set.insert((1, 1).into(), Direction::Left));
+        assert!(!set.insert((1, 1).into(), Direction::Up));
+        assert!(set.insert((5, -1).into(), Direction::Down));
+        assert!(set.contains(&(5, -1).into(), Direction::Down));
+        assert!(!set.contains(&(1, 1).into(), Direction::Right));
+        assert_eq!((3, 2), (set.len(), set.cell_count()));
+        assert_eq!(
+            vec![
+                ((1, 1).into(), Direction::Up),
+                ((1, 1).into(), Direction::Left),
+                ((5, -1).into(), Direction::Down)
+            ],
+            set.iter().collect::<Vec<_>>()
+        );
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.contains_cell(&(1, 1).into()));
+        assert!(set.insert((1, 1).into(), Direction::Up));
+
+        let mut unbounded = StateSet::unbounded();
+        assert!(unbounded.insert((-7, 3).into(), Direction::Right));
+        assert_eq!(1, unbounded.cells().count());
+    }
+}
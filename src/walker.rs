@@ -0,0 +1,391 @@
+use crate::states::StateSet;
+use crate::{Direction, Map, Point};
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+
+/// What a tile means to a guard walking onto it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Open,
+    Wall,
+    /// One-way: can only be entered moving in this direction, otherwise
+    /// it is a wall.
+    Arrow(Direction),
+    /// Entering one sends the guard to the other cell with the same label,
+    /// still facing the same way.
+    Teleport(char),
+    /// The guard keeps sliding forward until it is off the ice or blocked.
+    Ice,
+}
+
+/// Which way a guard faces after being blocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Right,
+    Left,
+    Around,
+}
+
+impl Turn {
+    fn apply(self, dir: Direction) -> Direction {
+        match self {
+            Turn::Right => dir.turn_right(),
+            Turn::Left => dir.turn_left(),
+            Turn::Around => dir.reverse(),
+        }
+    }
+}
+
+/// What happens when guards meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// Guards walk through each other.
+    Ignore,
+    /// A cell holding another guard blocks like a wall.
+    Block,
+    /// Guards that end a tick on the same cell both stop there.
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub turn: Turn,
+    pub collision: Collision,
+}
+
+/// Day 06's rules: turn right when blocked, guards get in each other's way.
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            turn: Turn::Right,
+            collision: Collision::Block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    Walking,
+    Exited,
+    Collided,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Guard {
+    pub pos: Point<i32>,
+    pub dir: Direction,
+    pub status: Status,
+}
+
+impl Guard {
+    pub fn new(pos: Point<i32>, dir: Direction) -> Self {
+        Guard {
+            pos,
+            dir,
+            status: Status::Walking,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub guards: Vec<Guard>,
+    /// Every (position, direction) any guard has been in.
+    pub visited: StateSet,
+    pub ticks: usize,
+    /// Whether the guards came back to a position they had all been in
+    /// together before, rather than all stopping.
+    pub looped: bool,
+}
+
+/// Moves guards over a map in lockstep, the meaning of each tile given by
+/// `cell`.
+pub struct Walker<'a, Tile, F> {
+    map: &'a Map<Tile>,
+    cell: F,
+    rules: Rules,
+    teleports: HashMap<Point<i32>, Point<i32>>,
+}
+
+impl<'a, Tile, F> Walker<'a, Tile, F>
+where
+    F: Fn(&Tile) -> Cell,
+{
+    /// Fails unless every teleport label is used by exactly two cells.
+    pub fn new(map: &'a Map<Tile>, cell: F, rules: Rules) -> Result<Self> {
+        let mut labels: HashMap<char, Vec<Point<i32>>> = HashMap::new();
+        for (point, tile) in map.iter() {
+            if let Cell::Teleport(label) = cell(tile) {
+                labels.entry(label).or_default().push(*point);
+            }
+        }
+        let mut teleports = HashMap::new();
+        for (label, ends) in labels {
+            let [a, b] = ends[..] else {
+                bail!("teleport {label:?} has {} ends, not 2", ends.len());
+            };
+            teleports.insert(a, b);
+            teleports.insert(b, a);
+        }
+        Ok(Walker {
+            map,
+            cell,
+            rules,
+            teleports,
+        })
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    fn enterable(&self, point: &Point<i32>, dir: Direction) -> bool {
+        match self.map.get(point).map(&self.cell) {
+            Some(Cell::Wall) => false,
+            Some(Cell::Arrow(only)) => only == dir,
+            _ => true,
+        }
+    }
+
+    // One guard's move for a tick, given where the others are.
+    fn advance(&self, guard: &mut Guard, others: &[Point<i32>], visited: &mut StateSet) {
+        let mut slid = false;
+        loop {
+            let ahead = guard.pos + guard.dir;
+            let Some(tile) = self.map.get(&ahead) else {
+                guard.status = Status::Exited;
+                return;
+            };
+            let cell = (self.cell)(tile);
+            // Where the guard would end up, the far end for a teleport.
+            let landing = match cell {
+                Cell::Teleport(_) => self.teleports[&ahead],
+                _ => ahead,
+            };
+            let blocked = !self.enterable(&ahead, guard.dir)
+                || (self.rules.collision == Collision::Block && others.contains(&landing));
+            if blocked {
+                // Sliding ends against the obstacle; the turn comes next tick.
+                if !slid {
+                    guard.dir = self.rules.turn.apply(guard.dir);
+                    visited.insert(guard.pos, guard.dir);
+                }
+                return;
+            }
+            guard.pos = landing;
+            visited.insert(guard.pos, guard.dir);
+            if cell != Cell::Ice {
+                return;
+            }
+            slid = true;
+        }
+    }
+
+    /// Moves every walking guard once, in order, each seeing the others'
+    /// positions as already updated. Under `Collision::Stop`, guards that
+    /// swap cells have met on the way and stop where they got to.
+    pub fn tick(&self, guards: &mut [Guard], visited: &mut StateSet) {
+        let before: Vec<Point<i32>> = guards.iter().map(|g| g.pos).collect();
+        for i in 0..guards.len() {
+            if guards[i].status != Status::Walking {
+                continue;
+            }
+            let others: Vec<Point<i32>> = guards
+                .iter()
+                .enumerate()
+                .filter(|(j, g)| *j != i && g.status != Status::Exited)
+                .map(|(_, g)| g.pos)
+                .collect();
+            self.advance(&mut guards[i], &others, visited);
+        }
+        if self.rules.collision == Collision::Stop {
+            let mut count: HashMap<Point<i32>, usize> = HashMap::new();
+            for guard in guards.iter().filter(|g| g.status != Status::Exited) {
+                *count.entry(guard.pos).or_default() += 1;
+            }
+            for guard in guards.iter_mut() {
+                if guard.status == Status::Walking && count[&guard.pos] > 1 {
+                    guard.status = Status::Collided;
+                }
+            }
+            for i in 0..guards.len() {
+                for j in i + 1..guards.len() {
+                    let swapped = guards[i].pos != before[i]
+                        && (guards[i].pos, guards[j].pos) == (before[j], before[i]);
+                    let walking = [i, j].map(|k| guards[k].status == Status::Walking);
+                    if swapped && walking == [true, true] {
+                        guards[i].status = Status::Collided;
+                        guards[j].status = Status::Collided;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs until no guard is walking or the guards repeat a joint state.
+    pub fn run(&self, mut guards: Vec<Guard>) -> Outcome {
+        let mut visited = StateSet::for_map(self.map);
+        for guard in &guards {
+            visited.insert(guard.pos, guard.dir);
+        }
+        let mut history = HashSet::from([guards.clone()]);
+        let mut ticks = 0;
+        let mut looped = false;
+        while guards.iter().any(|g| g.status == Status::Walking) {
+            self.tick(&mut guards, &mut visited);
+            ticks += 1;
+            if !history.insert(guards.clone()) {
+                looped = true;
+                break;
+            }
+        }
+        Outcome {
+            guards,
+            visited,
+            ticks,
+            looped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY06: &str = "\
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...";
+
+    fn cell(c: &char) -> Cell {
+        match c {
+            '#' => Cell::Wall,
+            '>' => Cell::Arrow(Direction::Right),
+            '<' => Cell::Arrow(Direction::Left),
+            '~' => Cell::Ice,
+            'a'..='z' => Cell::Teleport(*c),
+            _ => Cell::Open,
+        }
+    }
+
+    #[test]
+    fn day06_rules() {
+        let (mut map, start) = Map::<char>::parse_with_start(DAY06.as_bytes(), &'^').unwrap();
+        map.insert(start, '.');
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        let outcome = walker.run(vec![Guard::new(start, Direction::Up)]);
+        assert_eq!(41, outcome.visited.cell_count());
+        assert!(!outcome.looped);
+        assert_eq!(Status::Exited, outcome.guards[0].status);
+
+        map.insert((3, 6).into(), '#');
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        assert!(walker.run(vec![Guard::new(start, Direction::Up)]).looped);
+
+        let around = Rules {
+            turn: Turn::Around,
+            ..Rules::default()
+        };
+        let walker = Walker::new(&map, cell, around).unwrap();
+        let outcome = walker.run(vec![Guard::new(start, Direction::Up)]);
+        assert!(!outcome.looped);
+        // Up to the wall at the top, then straight back down and off.
+        assert_eq!(9, outcome.visited.cell_count());
+        assert!(outcome.visited.cells().all(|p| p.x == 4));
+    }
+
+    #[test]
+    fn special_tiles() {
+        let map: Map<char> = Map::parse(".a.\n.b.\n.a.".as_bytes()).unwrap();
+        assert!(Walker::new(&map, cell, Rules::default()).is_err());
+
+        let map: Map<char> = Map::parse(".a.<.#\n~~~...\n#...a.".as_bytes()).unwrap();
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        let mut guards = [Guard::new((0, 0).into(), Direction::Right)];
+        let mut visited = StateSet::for_map(&map);
+        walker.tick(&mut guards, &mut visited);
+        assert_eq!(Point::new(4, 2), guards[0].pos);
+        walker.tick(&mut guards, &mut visited);
+        walker.tick(&mut guards, &mut visited);
+        assert_eq!(Status::Exited, guards[0].status);
+
+        // The arrow only lets guards through heading left.
+        let mut guards = [Guard::new((2, 0).into(), Direction::Right)];
+        walker.tick(&mut guards, &mut visited);
+        assert_eq!(
+            (Point::new(2, 0), Direction::Down),
+            (guards[0].pos, guards[0].dir)
+        );
+        // Ice carries the guard to the far end in one tick.
+        let mut guards = [Guard::new((0, 1).into(), Direction::Right)];
+        walker.tick(&mut guards, &mut visited);
+        assert_eq!(Point::new(3, 1), guards[0].pos);
+
+        // Another guard on the far end of a teleport blocks it.
+        let map: Map<char> = Map::parse(".a.a.\n.....".as_bytes()).unwrap();
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        let mut guards = [
+            Guard::new((0, 0).into(), Direction::Right),
+            Guard::new((3, 0).into(), Direction::Right),
+        ];
+        walker.tick(&mut guards, &mut visited);
+        assert_eq!(
+            (Point::new(0, 0), Direction::Down),
+            (guards[0].pos, guards[0].dir)
+        );
+        assert_eq!(Point::new(4, 0), guards[1].pos);
+    }
+
+    #[test]
+    fn several_guards() {
+        let map: Map<char> = Map::parse(".....\n.....".as_bytes()).unwrap();
+        let guards = vec![
+            Guard::new((0, 0).into(), Direction::Right),
+            Guard::new((4, 0).into(), Direction::Left),
+        ];
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        let outcome = walker.run(guards.clone());
+        assert!(outcome.guards.iter().all(|g| g.status == Status::Exited));
+        // The second guard blocks the first, which turns down and walks off.
+        assert_eq!(Point::new(2, 1), outcome.guards[0].pos);
+
+        let stop = Rules {
+            collision: Collision::Stop,
+            ..Rules::default()
+        };
+        let outcome = Walker::new(&map, cell, stop).unwrap().run(guards);
+        assert!(outcome.guards.iter().all(|g| g.status == Status::Collided));
+        assert_eq!(Point::new(2, 0), outcome.guards[1].pos);
+
+        // Side by side and facing each other: they swap cells in one tick
+        // unless something stops them.
+        let facing = vec![
+            Guard::new((1, 0).into(), Direction::Right),
+            Guard::new((2, 0).into(), Direction::Left),
+        ];
+        let mut guards = facing.clone();
+        let walker = Walker::new(&map, cell, stop).unwrap();
+        walker.tick(&mut guards, &mut StateSet::for_map(&map));
+        assert!(guards.iter().all(|g| g.status == Status::Collided));
+        let mut guards = facing.clone();
+        let walker = Walker::new(&map, cell, Rules::default()).unwrap();
+        walker.tick(&mut guards, &mut StateSet::for_map(&map));
+        assert_eq!(
+            [Direction::Down, Direction::Up],
+            [guards[0].dir, guards[1].dir]
+        );
+        let ignore = Rules {
+            collision: Collision::Ignore,
+            ..Rules::default()
+        };
+        let outcome = Walker::new(&map, cell, ignore).unwrap().run(facing);
+        assert!(outcome.guards.iter().all(|g| g.status == Status::Exited));
+    }
+}
@@ -0,0 +1,96 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitChar, Token};
+
+/// Derives char conversions for a fieldless enum whose variants are tagged
+/// with `#[tile('#')]`. A variant may list several chars; the first one is
+/// used when converting back.
+///
+/// Generates `TryFrom<char>` (failing with `adv_code_2024::InvalidTile`),
+/// `From<Tile> for char`, `Display` and an `ALL` list of the variants.
+#[proc_macro_derive(Tile, attributes(tile))]
+pub fn derive_tile(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Tile can only be derived for enums",
+        ));
+    };
+
+    let mut parse_arms = Vec::new();
+    let mut char_arms = Vec::new();
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "Tile variants can't have fields",
+            ));
+        }
+        let attr = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("tile"))
+            .ok_or_else(|| syn::Error::new_spanned(variant, "missing #[tile('c')] attribute"))?;
+        let chars: Vec<LitChar> = attr
+            .parse_args_with(Punctuated::<LitChar, Token![,]>::parse_terminated)?
+            .into_iter()
+            .collect();
+        let Some(first) = chars.first() else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "#[tile] needs at least one char",
+            ));
+        };
+        parse_arms.push(quote! { #(#chars)|* => ::core::result::Result::Ok(#name::#ident), });
+        char_arms.push(quote! { #name::#ident => #first, });
+        variants.push(quote! { #name::#ident });
+    }
+
+    Ok(quote! {
+        impl ::core::convert::TryFrom<char> for #name {
+            type Error = ::adv_code_2024::InvalidTile;
+            fn try_from(c: char) -> ::core::result::Result<Self, Self::Error> {
+                match c {
+                    #(#parse_arms)*
+                    _ => ::core::result::Result::Err(::adv_code_2024::InvalidTile(c)),
+                }
+            }
+        }
+
+        impl ::core::convert::From<&#name> for char {
+            fn from(tile: &#name) -> char {
+                match tile {
+                    #(#char_arms)*
+                }
+            }
+        }
+
+        impl ::core::convert::From<#name> for char {
+            fn from(tile: #name) -> char {
+                char::from(&tile)
+            }
+        }
+
+        impl ::core::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Write::write_char(f, char::from(self))
+            }
+        }
+
+        impl #name {
+            pub const ALL: &'static [#name] = &[#(#variants),*];
+        }
+    })
+}